@@ -0,0 +1,953 @@
+use crate::to_kebab_case;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_quote, Data, DataEnum, DeriveInput, Fields, FieldsNamed, FieldsUnnamed, Generics};
+
+// The pieces that differ between the shapes of types AgentArg can be derived for.
+// `wit_type` evaluates to an `AnalysedType`, `to_value` has access to `self`,
+// and `from_value` decodes a `golem_wasm_rpc::Value` named `value` into `Result<Self, golem_agentic::ConversionError>`
+struct AgentArgBodies {
+    wit_type: TokenStream,
+    to_value: TokenStream,
+    from_value: TokenStream,
+}
+
+pub fn derive_agent_arg_impl(input: &DeriveInput) -> syn::Result<TokenStream> {
+    if is_serde_mode(input)? {
+        return serde_impls(input);
+    }
+
+    let bodies = match &input.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(named_fields) => {
+                let bodies = record_bodies(named_fields)?;
+                return Ok(generate_record_impls(input, bodies));
+            }
+            Fields::Unnamed(unnamed_fields) => {
+                FieldAttributes::reject_any(unnamed_fields)?;
+
+                if unnamed_fields.unnamed.len() == 1 {
+                    newtype_bodies(unnamed_fields)
+                } else {
+                    tuple_struct_bodies(unnamed_fields)
+                }
+            }
+            Fields::Unit => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "AgentArg cannot be derived for unit structs",
+                ))
+            }
+        },
+        Data::Enum(data_enum) => enum_bodies(input, data_enum)?,
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "AgentArg can only be derived for structs and enums",
+            ))
+        }
+    };
+
+    Ok(generate_impls(input, bodies))
+}
+
+fn generate_impls(input: &DeriveInput, bodies: AgentArgBodies) -> TokenStream {
+    let name = &input.ident;
+    let AgentArgBodies {
+        wit_type,
+        to_value,
+        from_value,
+    } = bodies;
+
+    let wit_type_generics = with_bound(&input.generics, parse_quote!(golem_agentic::ToWitType));
+    let (wit_type_impl_generics, ty_generics, wit_type_where_clause) =
+        wit_type_generics.split_for_impl();
+
+    let to_value_generics = with_bound(&input.generics, parse_quote!(golem_agentic::ToValue));
+    let (to_value_impl_generics, _, to_value_where_clause) = to_value_generics.split_for_impl();
+
+    let from_value_generics = with_bound(&input.generics, parse_quote!(golem_agentic::FromValue));
+    let (from_value_impl_generics, _, from_value_where_clause) =
+        from_value_generics.split_for_impl();
+
+    quote! {
+        impl #wit_type_impl_generics golem_agentic::ToWitType for #name #ty_generics #wit_type_where_clause {
+            fn get_wit_type() -> golem_wasm_rpc::WitType {
+                let analysed_type = #wit_type;
+                golem_wasm_rpc::WitType::from(analysed_type)
+            }
+        }
+
+        impl #to_value_impl_generics golem_agentic::ToValue for #name #ty_generics #to_value_where_clause {
+            fn to_value(&self) -> golem_wasm_rpc::Value {
+                #to_value
+            }
+        }
+
+        impl #from_value_impl_generics golem_agentic::FromValue for #name #ty_generics #from_value_where_clause {
+            fn from_value(value: golem_wasm_rpc::Value) -> Result<Self, golem_agentic::ConversionError> {
+                #from_value
+            }
+        }
+    }
+}
+
+// Structs with named fields can in addition be flattened into other records
+fn generate_record_impls(input: &DeriveInput, bodies: AgentArgBodies) -> TokenStream {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let impls = generate_impls(input, bodies);
+
+    quote! {
+        #impls
+
+        impl #impl_generics golem_agentic::AgentArgRecord for #name #ty_generics #where_clause {}
+    }
+}
+
+// `#[agent_arg(serde)]` delegates to the type's `Serialize` and `Deserialize` implementations,
+// in which case serde's own attributes apply instead of the `agent_arg` field attributes
+fn is_serde_mode(input: &DeriveInput) -> syn::Result<bool> {
+    let mut serde = false;
+
+    for attr in &input.attrs {
+        if !attr.path().is_ident("agent_arg") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("serde") {
+                serde = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `serde`"))
+            }
+        })?;
+    }
+
+    Ok(serde)
+}
+
+fn serde_impls(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let fields: Vec<&syn::Field> = match &input.data {
+        Data::Struct(data_struct) => data_struct.fields.iter().collect(),
+        Data::Enum(data_enum) => data_enum.variants.iter().flat_map(|v| &v.fields).collect(),
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "AgentArg can only be derived for structs and enums",
+            ))
+        }
+    };
+
+    let variant_attrs = match &input.data {
+        Data::Enum(data_enum) => data_enum.variants.iter().flat_map(|v| &v.attrs).collect(),
+        _ => vec![],
+    };
+
+    let field_attrs = fields.iter().flat_map(|f| &f.attrs);
+
+    if let Some(attr) = field_attrs
+        .chain(variant_attrs)
+        .find(|a| a.path().is_ident("agent_arg"))
+    {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "`agent_arg` attributes cannot be used together with `#[agent_arg(serde)]`, use serde attributes instead",
+        ));
+    }
+
+    let name = &input.ident;
+
+    let mut serialize_generics = input.generics.clone();
+    serialize_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(Self: golem_agentic::serde_bridge::Serialize));
+    let (serialize_impl_generics, ty_generics, serialize_where_clause) =
+        serialize_generics.split_for_impl();
+
+    let mut deserialize_generics = input.generics.clone();
+    deserialize_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(Self: golem_agentic::serde_bridge::DeserializeOwned));
+    let (deserialize_impl_generics, _, deserialize_where_clause) =
+        deserialize_generics.split_for_impl();
+
+    Ok(quote! {
+        impl #deserialize_impl_generics golem_agentic::ToWitType for #name #ty_generics #deserialize_where_clause {
+            fn get_wit_type() -> golem_wasm_rpc::WitType {
+                let analysed_type = golem_agentic::serde_bridge::wit_type::<Self>()
+                    .unwrap_or_else(|e| panic!("Failed to infer the WIT type of {}: {}", stringify!(#name), e));
+                golem_wasm_rpc::WitType::from(analysed_type)
+            }
+        }
+
+        impl #serialize_impl_generics golem_agentic::ToValue for #name #ty_generics #serialize_where_clause {
+            fn to_value(&self) -> golem_wasm_rpc::Value {
+                golem_agentic::serde_bridge::to_value(self)
+                    .unwrap_or_else(|e| panic!("Failed to convert {} to a WIT value: {}", stringify!(#name), e))
+            }
+        }
+
+        impl #deserialize_impl_generics golem_agentic::FromValue for #name #ty_generics #deserialize_where_clause {
+            fn from_value(value: golem_wasm_rpc::Value) -> Result<Self, golem_agentic::ConversionError> {
+                golem_agentic::serde_bridge::from_value(value)
+            }
+        }
+    })
+}
+
+// Every type parameter has to satisfy the trait being implemented, as the fields using it are
+// converted with the same trait
+fn with_bound(generics: &Generics, bound: syn::TypeParamBound) -> Generics {
+    let mut generics = generics.clone();
+
+    for param in generics.type_params_mut() {
+        param.bounds.push(bound.clone());
+    }
+
+    generics
+}
+
+fn wit_type_of(ty: &syn::Type) -> TokenStream {
+    quote! {
+        golem_wasm_ast::analysis::AnalysedType::from(<#ty as golem_agentic::ToWitType>::get_wit_type())
+    }
+}
+
+fn record_bodies(fields: &FieldsNamed) -> syn::Result<AgentArgBodies> {
+    let record = RecordFields::new(fields)?;
+
+    let accessors: Vec<_> = record
+        .fields
+        .iter()
+        .map(|field| {
+            let ident = &field.ident;
+            quote! { &self.#ident }
+        })
+        .collect();
+
+    let wit_type = record.wit_type();
+    let to_value = record.to_value(&accessors);
+    let decode = record.decode_values(quote! { __agent_arg_record });
+    let field_idents = record.idents();
+
+    Ok(AgentArgBodies {
+        wit_type,
+        to_value,
+        from_value: quote! {
+            match value {
+                golem_wasm_rpc::Value::Record(__agent_arg_record) => {
+                    #decode
+
+                    Ok(Self {
+                        #(#field_idents),*
+                    })
+                }
+                other => Err(golem_agentic::ConversionError::type_mismatch("record", &other))
+            }
+        },
+    })
+}
+
+struct FieldAttributes {
+    rename: Option<String>,
+    skip: bool,
+    default: Option<FieldDefault>,
+    flatten: bool,
+}
+
+enum FieldDefault {
+    Trait,
+    Path(syn::ExprPath),
+}
+
+impl FieldAttributes {
+    fn parse(field: &syn::Field) -> syn::Result<FieldAttributes> {
+        let mut result = FieldAttributes {
+            rename: None,
+            skip: false,
+            default: None,
+            flatten: false,
+        };
+
+        for attr in &field.attrs {
+            if !attr.path().is_ident("agent_arg") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    result.rename = Some(lit.value());
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    result.skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    if meta.input.peek(syn::Token![=]) {
+                        let lit: syn::LitStr = meta.value()?.parse()?;
+                        result.default = Some(FieldDefault::Path(lit.parse()?));
+                    } else {
+                        result.default = Some(FieldDefault::Trait);
+                    }
+                    Ok(())
+                } else if meta.path.is_ident("flatten") {
+                    result.flatten = true;
+                    Ok(())
+                } else {
+                    Err(meta.error(
+                        "expected one of `rename = \"...\"`, `skip`, `default`, `default = \"...\"` or `flatten`",
+                    ))
+                }
+            })?;
+        }
+
+        if result.skip && (result.rename.is_some() || result.flatten) {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`skip` cannot be combined with `rename` or `flatten`",
+            ));
+        }
+
+        if result.flatten && (result.rename.is_some() || result.default.is_some()) {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`flatten` cannot be combined with `rename` or `default`",
+            ));
+        }
+
+        Ok(result)
+    }
+
+    fn reject_any(fields: &FieldsUnnamed) -> syn::Result<()> {
+        for field in &fields.unnamed {
+            if let Some(attr) = field.attrs.iter().find(|a| a.path().is_ident("agent_arg")) {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`agent_arg` field attributes are only supported on named fields",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct RecordField {
+    ident: syn::Ident,
+    ty: syn::Type,
+    name: String,
+    attributes: FieldAttributes,
+}
+
+// Named fields, used both by structs and by enum cases with named fields
+struct RecordFields {
+    fields: Vec<RecordField>,
+}
+
+impl RecordFields {
+    fn new(fields: &FieldsNamed) -> syn::Result<RecordFields> {
+        let fields = fields
+            .named
+            .iter()
+            .map(|field| {
+                let ident = field.ident.clone().unwrap();
+                let attributes = FieldAttributes::parse(field)?;
+                // Field names are kebab-cased like case names, unless renamed
+                let name = attributes
+                    .rename
+                    .clone()
+                    .unwrap_or_else(|| to_kebab_case(&ident.to_string()));
+
+                Ok(RecordField {
+                    ident,
+                    ty: field.ty.clone(),
+                    name,
+                    attributes,
+                })
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+
+        Ok(RecordFields { fields })
+    }
+
+    fn idents(&self) -> Vec<&syn::Ident> {
+        self.fields.iter().map(|field| &field.ident).collect()
+    }
+
+    // Patterns binding the fields to variables named after them, ignoring the skipped fields
+    fn bindings(&self) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .map(|field| {
+                let ident = &field.ident;
+
+                if field.attributes.skip {
+                    quote! { #ident: _ }
+                } else {
+                    quote! { #ident }
+                }
+            })
+            .collect()
+    }
+
+    fn wit_type(&self) -> TokenStream {
+        let pushes = self.fields.iter().filter(|f| !f.attributes.skip).map(|field| {
+            let name = &field.name;
+            let ty = &field.ty;
+            let typ = wit_type_of(ty);
+
+            if field.attributes.flatten {
+                // Flattening anything but a record is rejected at compile time
+                quote! {
+                    golem_agentic::assert_agent_arg_record::<#ty>();
+
+                    match golem_wasm_ast::analysis::AnalysedType::from(<#ty as golem_agentic::ToWitType>::get_wit_type()) {
+                        golem_wasm_ast::analysis::AnalysedType::Record(record) => fields.extend(record.fields),
+                        _ => panic!("Field '{}' is flattened, but its type is not a record", #name),
+                    }
+                }
+            } else {
+                quote! {
+                    fields.push(golem_wasm_ast::analysis::NameTypePair {
+                        name: #name.to_string(),
+                        typ: #typ,
+                    });
+                }
+            }
+        });
+
+        quote! {
+            {
+                let mut fields: Vec<golem_wasm_ast::analysis::NameTypePair> = Vec::new();
+                #(#pushes)*
+                golem_wasm_ast::analysis::analysed_type::record(fields)
+            }
+        }
+    }
+
+    // `accessors` evaluate to references to the fields, in the same order
+    fn to_value(&self, accessors: &[TokenStream]) -> TokenStream {
+        let pushes = self
+            .fields
+            .iter()
+            .zip(accessors)
+            .filter(|(field, _)| !field.attributes.skip)
+            .map(|(field, accessor)| {
+                if field.attributes.flatten {
+                    let name = &field.name;
+
+                    quote! {
+                        match golem_agentic::ToValue::to_value(#accessor) {
+                            golem_wasm_rpc::Value::Record(__agent_arg_nested) => __agent_arg_values.extend(__agent_arg_nested),
+                            _ => panic!("Field '{}' is flattened, but its value is not a record", #name),
+                        }
+                    }
+                } else {
+                    quote! {
+                        __agent_arg_values.push(golem_agentic::ToValue::to_value(#accessor));
+                    }
+                }
+            });
+
+        quote! {
+            {
+                let mut __agent_arg_values: Vec<golem_wasm_rpc::Value> = Vec::new();
+                #(#pushes)*
+                golem_wasm_rpc::Value::Record(__agent_arg_values)
+            }
+        }
+    }
+
+    // Binds every field to a variable named after it, reading the record's values positionally.
+    // Trailing fields that are missing from the record are filled in if they have a default.
+    // The other variables are prefixed, so that they are not shadowed by the fields.
+    fn decode_values(&self, values: TokenStream) -> TokenStream {
+        let decodes = self.fields.iter().map(|field| {
+            let ident = &field.ident;
+            let ty = &field.ty;
+            let name = &field.name;
+
+            if field.attributes.skip {
+                return quote! {
+                    let #ident: #ty = ::core::default::Default::default();
+                };
+            }
+
+            if field.attributes.flatten {
+                return quote! {
+                    let __agent_arg_field_count = match golem_wasm_ast::analysis::AnalysedType::from(
+                        <#ty as golem_agentic::ToWitType>::get_wit_type()
+                    ) {
+                        golem_wasm_ast::analysis::AnalysedType::Record(record) => record.fields.len(),
+                        _ => panic!("Field '{}' is flattened, but its type is not a record", #name),
+                    };
+                    let __agent_arg_nested: Vec<golem_wasm_rpc::Value> =
+                        __agent_arg_values.by_ref().take(__agent_arg_field_count).collect();
+                    let #ident: #ty = golem_agentic::FromValue::from_value(golem_wasm_rpc::Value::Record(__agent_arg_nested))?;
+                };
+            }
+
+            let missing = match &field.attributes.default {
+                Some(FieldDefault::Trait) => quote! { ::core::default::Default::default() },
+                Some(FieldDefault::Path(path)) => quote! { #path() },
+                None => quote! { return Err(golem_agentic::ConversionError::missing_field(#name)) },
+            };
+
+            quote! {
+                let #ident: #ty = match __agent_arg_values.next() {
+                    Some(value) => golem_agentic::FromValue::from_value(value)
+                        .map_err(|e| e.at_field(#name))?,
+                    None => #missing,
+                };
+            }
+        });
+
+        quote! {
+            let mut __agent_arg_values = #values.into_iter();
+
+            #(#decodes)*
+
+            let __agent_arg_extra_fields = __agent_arg_values.count();
+            if __agent_arg_extra_fields > 0 {
+                return Err(golem_agentic::ConversionError::custom(format!(
+                    "record has {} more fields than expected",
+                    __agent_arg_extra_fields
+                )));
+            }
+        }
+    }
+}
+
+// A newtype is transparent, i.e, it has the same WIT representation as the type it wraps
+fn newtype_bodies(fields: &FieldsUnnamed) -> AgentArgBodies {
+    let ty = &fields.unnamed[0].ty;
+
+    AgentArgBodies {
+        wit_type: wit_type_of(ty),
+        to_value: quote! {
+            golem_agentic::ToValue::to_value(&self.0)
+        },
+        from_value: quote! {
+            <#ty as golem_agentic::FromValue>::from_value(value).map(Self)
+        },
+    }
+}
+
+fn tuple_struct_bodies(fields: &FieldsUnnamed) -> AgentArgBodies {
+    let types: Vec<_> = fields.unnamed.iter().map(|f| &f.ty).collect();
+    let indices: Vec<_> = (0..types.len()).map(syn::Index::from).collect();
+    let bindings: Vec<_> = (0..types.len())
+        .map(|i| format_ident!("field_{}", i))
+        .collect();
+    let item_types: Vec<_> = types.iter().map(|ty| wit_type_of(ty)).collect();
+    let positions: Vec<usize> = (0..types.len()).collect();
+    let field_count = types.len();
+
+    AgentArgBodies {
+        wit_type: quote! {
+            golem_wasm_ast::analysis::analysed_type::tuple(vec![
+                #(#item_types),*
+            ])
+        },
+        to_value: quote! {
+            golem_wasm_rpc::Value::Tuple(vec![
+                #(golem_agentic::ToValue::to_value(&self.#indices)),*
+            ])
+        },
+        from_value: quote! {
+            match value {
+                golem_wasm_rpc::Value::Tuple(values) if values.len() == #field_count => {
+                    let mut values = values.into_iter();
+                    #(
+                        let #bindings = golem_agentic::FromValue::from_value(values.next().unwrap())
+                            .map_err(|e| e.at_index(#positions))?;
+                    )*
+                    Ok(Self(#(#bindings),*))
+                }
+                golem_wasm_rpc::Value::Tuple(values) => {
+                    Err(golem_agentic::ConversionError::length_mismatch(#field_count, values.len()))
+                }
+                other => Err(golem_agentic::ConversionError::type_mismatch("tuple", &other))
+            }
+        },
+    }
+}
+
+// Enums with only unit variants become WIT enums, anything else becomes a WIT variant
+fn enum_bodies(input: &DeriveInput, data_enum: &DataEnum) -> syn::Result<AgentArgBodies> {
+    if data_enum.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            input,
+            "AgentArg cannot be derived for enums without variants",
+        ));
+    }
+
+    let is_unit_only = data_enum
+        .variants
+        .iter()
+        .all(|variant| matches!(variant.fields, Fields::Unit));
+
+    if is_unit_only {
+        unit_enum_bodies(input, data_enum)
+    } else {
+        variant_bodies(input, data_enum)
+    }
+}
+
+// Only `rename` is supported on enum variants
+fn case_name(variant: &syn::Variant) -> syn::Result<String> {
+    let mut rename = None;
+
+    for attr in &variant.attrs {
+        if !attr.path().is_ident("agent_arg") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                rename = Some(lit.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename = \"...\"`"))
+            }
+        })?;
+    }
+
+    Ok(rename.unwrap_or_else(|| to_kebab_case(&variant.ident.to_string())))
+}
+
+fn unit_enum_bodies(input: &DeriveInput, data_enum: &DataEnum) -> syn::Result<AgentArgBodies> {
+    let enum_name_str = input.ident.to_string();
+
+    let variant_idents: Vec<_> = data_enum.variants.iter().map(|v| &v.ident).collect();
+    let case_names: Vec<String> = data_enum
+        .variants
+        .iter()
+        .map(case_name)
+        .collect::<syn::Result<_>>()?;
+    let case_indices: Vec<u32> = (0..variant_idents.len() as u32).collect();
+
+    Ok(AgentArgBodies {
+        wit_type: quote! {
+            golem_wasm_ast::analysis::analysed_type::r#enum(&[
+                #(#case_names),*
+            ])
+        },
+        to_value: quote! {
+            match self {
+                #(Self::#variant_idents => golem_wasm_rpc::Value::Enum(#case_indices)),*
+            }
+        },
+        from_value: quote! {
+            match value {
+                #(golem_wasm_rpc::Value::Enum(#case_indices) => Ok(Self::#variant_idents),)*
+                golem_wasm_rpc::Value::Enum(idx) => {
+                    Err(golem_agentic::ConversionError::unknown_case(#enum_name_str, idx))
+                }
+                other => Err(golem_agentic::ConversionError::type_mismatch("enum", &other))
+            }
+        },
+    })
+}
+
+fn variant_bodies(input: &DeriveInput, data_enum: &DataEnum) -> syn::Result<AgentArgBodies> {
+    let enum_name_str = input.ident.to_string();
+    let case_count = data_enum.variants.len() as u32;
+
+    let mut wit_type_cases = Vec::new();
+    let mut to_value_arms = Vec::new();
+    let mut from_value_arms = Vec::new();
+
+    for (idx, variant) in data_enum.variants.iter().enumerate() {
+        let variant_ident = &variant.ident;
+        let case_name = case_name(variant)?;
+        let case_idx = idx as u32;
+
+        match &variant.fields {
+            Fields::Unit => {
+                wit_type_cases.push(quote! {
+                    golem_wasm_ast::analysis::analysed_type::unit_case(#case_name)
+                });
+
+                to_value_arms.push(quote! {
+                    Self::#variant_ident => golem_wasm_rpc::Value::Variant {
+                        case_idx: #case_idx,
+                        case_value: None,
+                    }
+                });
+
+                from_value_arms.push(quote! {
+                    (#case_idx, None) => Ok(Self::#variant_ident)
+                });
+            }
+
+            // A single unnamed field is the payload itself
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                FieldAttributes::reject_any(fields)?;
+
+                let typ = wit_type_of(&fields.unnamed[0].ty);
+
+                wit_type_cases.push(quote! {
+                    golem_wasm_ast::analysis::analysed_type::case(#case_name, #typ)
+                });
+
+                to_value_arms.push(quote! {
+                    Self::#variant_ident(inner) => golem_wasm_rpc::Value::Variant {
+                        case_idx: #case_idx,
+                        case_value: Some(Box::new(golem_agentic::ToValue::to_value(inner))),
+                    }
+                });
+
+                from_value_arms.push(quote! {
+                    (#case_idx, Some(payload)) => {
+                        let inner = golem_agentic::FromValue::from_value(*payload)
+                            .map_err(|e| e.at_field(#case_name))?;
+                        Ok(Self::#variant_ident(inner))
+                    }
+                });
+            }
+
+            // Multiple unnamed fields are carried as a tuple
+            Fields::Unnamed(fields) => {
+                FieldAttributes::reject_any(fields)?;
+
+                let item_types: Vec<_> =
+                    fields.unnamed.iter().map(|f| wit_type_of(&f.ty)).collect();
+                let bindings: Vec<_> = (0..item_types.len())
+                    .map(|i| format_ident!("field_{}", i))
+                    .collect();
+                let positions: Vec<usize> = (0..item_types.len()).collect();
+                let field_count = item_types.len();
+
+                wit_type_cases.push(quote! {
+                    golem_wasm_ast::analysis::analysed_type::case(
+                        #case_name,
+                        golem_wasm_ast::analysis::analysed_type::tuple(vec![
+                            #(#item_types),*
+                        ])
+                    )
+                });
+
+                to_value_arms.push(quote! {
+                    Self::#variant_ident(#(#bindings),*) => golem_wasm_rpc::Value::Variant {
+                        case_idx: #case_idx,
+                        case_value: Some(Box::new(golem_wasm_rpc::Value::Tuple(vec![
+                            #(golem_agentic::ToValue::to_value(#bindings)),*
+                        ]))),
+                    }
+                });
+
+                from_value_arms.push(quote! {
+                    (#case_idx, Some(payload)) => match *payload {
+                        golem_wasm_rpc::Value::Tuple(values) if values.len() == #field_count => {
+                            let mut values = values.into_iter();
+                            #(
+                                let #bindings = golem_agentic::FromValue::from_value(values.next().unwrap())
+                                    .map_err(|e| e.at_index(#positions).at_field(#case_name))?;
+                            )*
+                            Ok(Self::#variant_ident(#(#bindings),*))
+                        }
+                        golem_wasm_rpc::Value::Tuple(values) => Err(
+                            golem_agentic::ConversionError::length_mismatch(#field_count, values.len())
+                                .at_field(#case_name)
+                        ),
+                        other => Err(
+                            golem_agentic::ConversionError::type_mismatch("tuple", &other).at_field(#case_name)
+                        )
+                    }
+                });
+            }
+
+            // Named fields are carried as a record
+            Fields::Named(fields) => {
+                let record = RecordFields::new(fields)?;
+                let idents = record.idents();
+                let bindings = record.bindings();
+                let accessors: Vec<_> = idents.iter().map(|ident| quote! { #ident }).collect();
+
+                let typ = record.wit_type();
+                let payload = record.to_value(&accessors);
+                let decode = record.decode_values(quote! { __agent_arg_record });
+
+                wit_type_cases.push(quote! {
+                    golem_wasm_ast::analysis::analysed_type::case(#case_name, #typ)
+                });
+
+                to_value_arms.push(quote! {
+                    Self::#variant_ident { #(#bindings),* } => golem_wasm_rpc::Value::Variant {
+                        case_idx: #case_idx,
+                        case_value: Some(Box::new(#payload)),
+                    }
+                });
+
+                from_value_arms.push(quote! {
+                    (#case_idx, Some(payload)) => match *payload {
+                        golem_wasm_rpc::Value::Record(__agent_arg_record) => {
+                            let __agent_arg_decoded: Result<Self, golem_agentic::ConversionError> = (|| {
+                                #decode
+
+                                Ok(Self::#variant_ident { #(#idents),* })
+                            })();
+
+                            __agent_arg_decoded.map_err(|e| e.at_field(#case_name))
+                        }
+                        other => Err(
+                            golem_agentic::ConversionError::type_mismatch("record", &other).at_field(#case_name)
+                        )
+                    }
+                });
+            }
+        }
+    }
+
+    Ok(AgentArgBodies {
+        wit_type: quote! {
+            golem_wasm_ast::analysis::analysed_type::variant(vec![
+                #(#wit_type_cases),*
+            ])
+        },
+        to_value: quote! {
+            match self {
+                #(#to_value_arms),*
+            }
+        },
+        from_value: quote! {
+            match value {
+                golem_wasm_rpc::Value::Variant { case_idx, case_value } => match (case_idx, case_value) {
+                    #(#from_value_arms,)*
+                    (case_idx, _) if case_idx < #case_count => Err(golem_agentic::ConversionError::custom(
+                        format!("unexpected payload for case {} of {}", case_idx, #enum_name_str),
+                    )),
+                    (case_idx, _) => Err(golem_agentic::ConversionError::unknown_case(#enum_name_str, case_idx)),
+                },
+                other => Err(golem_agentic::ConversionError::type_mismatch("variant", &other))
+            }
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn derive_error(input: DeriveInput) -> String {
+        derive_agent_arg_impl(&input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn unit_structs_are_rejected() {
+        assert_eq!(
+            derive_error(parse_quote! { struct Marker; }),
+            "AgentArg cannot be derived for unit structs"
+        );
+    }
+
+    #[test]
+    fn unions_are_rejected() {
+        assert_eq!(
+            derive_error(parse_quote! { union Bits { int: u32, float: f32 } }),
+            "AgentArg can only be derived for structs and enums"
+        );
+    }
+
+    #[test]
+    fn field_attributes_of_tuple_structs_are_rejected() {
+        assert_eq!(
+            derive_error(parse_quote! { struct Point(#[agent_arg(skip)] i32, i32); }),
+            "`agent_arg` field attributes are only supported on named fields"
+        );
+    }
+
+    #[test]
+    fn conflicting_field_attributes_are_rejected() {
+        assert_eq!(
+            derive_error(parse_quote! {
+                struct Order {
+                    #[agent_arg(skip, flatten)]
+                    audit: Audit,
+                }
+            }),
+            "`skip` cannot be combined with `rename` or `flatten`"
+        );
+        assert_eq!(
+            derive_error(parse_quote! {
+                struct Order {
+                    #[agent_arg(flatten, default)]
+                    audit: Audit,
+                }
+            }),
+            "`flatten` cannot be combined with `rename` or `default`"
+        );
+    }
+
+    #[test]
+    fn unknown_field_attributes_are_rejected() {
+        assert_eq!(
+            derive_error(parse_quote! {
+                struct Order {
+                    #[agent_arg(alias = "id")]
+                    order_id: String,
+                }
+            }),
+            "expected one of `rename = \"...\"`, `skip`, `default`, `default = \"...\"` or `flatten`"
+        );
+    }
+
+    #[test]
+    fn enums_without_variants_are_rejected() {
+        assert_eq!(
+            derive_error(parse_quote! { enum Never {} }),
+            "AgentArg cannot be derived for enums without variants"
+        );
+    }
+
+    #[test]
+    fn unknown_variant_attributes_are_rejected() {
+        assert_eq!(
+            derive_error(parse_quote! {
+                enum Unit {
+                    #[agent_arg(skip)]
+                    Celsius,
+                    Fahrenheit,
+                }
+            }),
+            "expected `rename = \"...\"`"
+        );
+    }
+
+    #[test]
+    fn field_attributes_of_tuple_cases_are_rejected() {
+        assert_eq!(
+            derive_error(parse_quote! {
+                enum Command {
+                    Move(#[agent_arg(default)] i32, i32),
+                }
+            }),
+            "`agent_arg` field attributes are only supported on named fields"
+        );
+    }
+
+    #[test]
+    fn only_records_can_be_flattened() {
+        let expansion = derive_agent_arg_impl(&parse_quote! {
+            struct Location {
+                city: String,
+            }
+        })
+        .unwrap()
+        .to_string();
+
+        assert!(expansion.contains("AgentArgRecord for Location"));
+
+        let expansion = derive_agent_arg_impl(&parse_quote! { struct CustomerId(String); })
+            .unwrap()
+            .to_string();
+
+        assert!(!expansion.contains("AgentArgRecord"));
+    }
+}
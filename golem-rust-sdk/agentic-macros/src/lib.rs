@@ -4,33 +4,23 @@ use proc_macro::TokenStream;
 use std::path::PathBuf;
 use proc_macro2::Ident;
 use quote::{format_ident, quote};
-use syn::{parse_macro_input, Data, DeriveInput, Fields, Type};
+use syn::{parse_macro_input, DeriveInput, Type};
 use std::path::Path;
 
+mod agent_arg;
+
 
 #[proc_macro_attribute]
-pub fn agent_definition(_attrs: TokenStream, item: TokenStream) -> TokenStream {
+pub fn agent_definition(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    let interface = match remote_interface(attrs.into()) {
+        Ok(interface) => interface,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
     let tr = syn::parse_macro_input!(item as syn::ItemTrait);
-    let generics = &tr.generics;
-    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let tr_name = tr.ident.clone();
     let tr_name_str = tr_name.to_string();
-    let tr_name_str_kebab = to_kebab_case(&tr_name_str);
-    let fn_suffix = &tr_name.to_string().to_lowercase();
-    let fn_name = format_ident!("register_generic_agent_type_{}", fn_suffix); // may be ctor is not required. But works now
-
-    let agent_type = get_agent_type(&tr);
-
-    let register_fn = quote! {
-        #[::ctor::ctor]
-        fn #fn_name() {
-            golem_agentic::agent_registry::register_generic_agent_type(
-               #tr_name_str_kebab.to_string(),
-                #agent_type
-            );
-        }
-    };
 
     let registry_path: PathBuf = dirs::cache_dir()
         .expect("Could not find cache dir")
@@ -76,6 +66,65 @@ pub fn agent_definition(_attrs: TokenStream, item: TokenStream) -> TokenStream {
         })
         .collect();
 
+    expand_agent_definition(&tr, &interface, &constructor_params).into()
+}
+
+// The `interface = "..."` the remote clients call the agent through
+fn remote_interface(attrs: proc_macro2::TokenStream) -> syn::Result<String> {
+    let mut interface = DEFAULT_REMOTE_INTERFACE.to_string();
+
+    let attrs_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("interface") {
+            let lit: syn::LitStr = meta.value()?.parse()?;
+            interface = lit.value();
+            Ok(())
+        } else {
+            Err(meta.error("expected `interface = \"...\"`"))
+        }
+    });
+
+    syn::parse::Parser::parse2(attrs_parser, attrs)?;
+
+    Ok(interface)
+}
+
+// Emits the trait along with the registration of its agent type and its `Remote*` client
+fn expand_agent_definition(
+    tr: &syn::ItemTrait,
+    interface: &str,
+    constructor_params: &[(Ident, Type)],
+) -> proc_macro2::TokenStream {
+    let generics = &tr.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let tr_name = tr.ident.clone();
+    let tr_name_str = tr_name.to_string();
+    let tr_name_str_kebab = to_kebab_case(&tr_name_str);
+    let fn_suffix = &tr_name.to_string().to_lowercase();
+    let fn_name = format_ident!("register_generic_agent_type_{}", fn_suffix); // may be ctor is not required. But works now
+
+    let agent_type = match get_agent_type(tr) {
+        Ok(agent_type) => agent_type,
+        Err(e) => {
+            let error = e.to_compile_error();
+            return quote! {
+                #[allow(async_fn_in_trait)]
+                #tr
+                #error
+            };
+        }
+    };
+
+    let register_fn = quote! {
+        #[::ctor::ctor]
+        fn #fn_name() {
+            golem_agentic::agent_registry::register_generic_agent_type(
+               #tr_name_str_kebab.to_string(),
+                #agent_type
+            );
+        }
+    };
+
     let constructor_params_decl = constructor_params.iter().map(|(name, ty)| {
         quote! { #name: #ty }
     });
@@ -91,17 +140,17 @@ pub fn agent_definition(_attrs: TokenStream, item: TokenStream) -> TokenStream {
 
     let remote_trait_name = format_ident!("Remote{}", tr_name);
 
+    let remote_constructor_name = remote_constructor_name(interface, &tr_name_str_kebab);
+    let remote_get_agent_name = remote_get_agent_name(interface);
+
     let method_impls = tr.items.iter().filter_map(|item| {
         if let syn::TraitItem::Fn(method) = item {
             let method_name = &method.sig.ident;
             let method_name_str = method_name.to_string();
             let method_name_str_kebab = to_kebab_case(&method_name_str);
 
-            let wrapped_component_method_name_str = format!(
-                "golem:simulated-agentic/simulated-agent.{{[method]{}.{}}}",
-                tr_name_str_kebab,
-                method_name_str_kebab
-            );
+            let wrapped_component_method_name_str =
+                remote_method_name(interface, &tr_name_str_kebab, &method_name_str_kebab);
 
             let wrapped_component_method_name = {
                 quote! {
@@ -137,55 +186,94 @@ pub fn agent_definition(_attrs: TokenStream, item: TokenStream) -> TokenStream {
                 syn::ReturnType::Default => quote! { () },
             };
 
+            let (remote_return_type, decode_result) = if let Some(item_type) = stream_item_type(&method.sig.output) {
+                let get_next_name = remote_get_next_name(interface, &tr_name_str_kebab);
+
+                (
+                    quote! { Result<golem_agentic::AgentStream<Result<#item_type, golem_agentic::RemoteAgentError>>, golem_agentic::RemoteAgentError> },
+                    quote! {
+                        golem_agentic::remote::decode_stream::<#item_type>(__result, {
+                            let __handle = golem_wasm_rpc::WitValue::from(self.handle.clone());
+                            move || __rpc.invoke_and_await(#get_next_name, &[__handle.clone()])
+                        })
+                    },
+                )
+            } else if let Some((ok_type, err_type)) = result_types(&method.sig.output) {
+                (
+                    quote! { Result<#ok_type, golem_agentic::RemoteAgentError<#err_type>> },
+                    quote! { golem_agentic::remote::decode_result::<#ok_type, #err_type>(__result) },
+                )
+            } else if is_unit_return(&method.sig.output) {
+                (
+                    quote! { Result<(), golem_agentic::RemoteAgentError> },
+                    quote! { golem_agentic::remote::decode_completed(__result) },
+                )
+            } else {
+                (
+                    quote! { Result<#return_type, golem_agentic::RemoteAgentError> },
+                    quote! { golem_agentic::remote::decode_emitted::<#return_type>(__result) },
+                )
+            };
+
+            let typed_inputs: Vec<_> = method
+                .sig
+                .inputs
+                .iter()
+                .filter(|arg| matches!(arg, syn::FnArg::Typed(_)))
+                .collect();
+
+            let trigger_method_name = format_ident!("trigger_{}", method_name);
+            let schedule_method_name = format_ident!("schedule_{}", method_name);
+
+            let build_inputs = quote! {
+                let __input_arg_values : Vec<golem_wasm_rpc::Value> = #input_vec_wit;
+
+                let mut __inputs = vec![
+                    golem_wasm_rpc::WitValue::from(self.handle.clone()),
+                ];
+
+                for __arg in __input_arg_values.iter() {
+                   let __arg_wit_value: golem_wasm_rpc::WitValue = golem_wasm_rpc::WitValue::from(__arg.clone());
+                   __inputs.push(__arg_wit_value);
+                }
+            };
+
             Some(quote! {
-                pub async fn #method_name(#(#inputs),*) -> #return_type {
-                    let rpc = golem_wasm_rpc::WasmRpc::new(&self.worker_id);
-                    let mut inputs = vec![
-                        golem_wasm_rpc::WitValue::from(self.handle.clone()),
-                    ];
+                pub async fn #method_name(#(#inputs),*) -> #remote_return_type {
+                    let __rpc = golem_wasm_rpc::WasmRpc::new(&self.worker_id);
 
-                    let input_arg_values : Vec<golem_wasm_rpc::Value> = #input_vec_wit;
+                    #build_inputs
 
-                    for arg in input_arg_values.iter() {
-                       let arg_wit_value: golem_wasm_rpc::WitValue = golem_wasm_rpc::WitValue::from(arg.clone());
-                       inputs.push(arg_wit_value);
-                    }
+                    let __result = golem_agentic::remote::invoke_with_options(&self.options, || {
+                        __rpc.async_invoke_and_await(#wrapped_component_method_name, __inputs.as_slice())
+                    }).await;
 
-                    let result: golem_wasm_rpc::WitValue = rpc.invoke_and_await(
-                        #wrapped_component_method_name,
-                        inputs.as_slice()
-                    ).map_err(|e| format!("Failed to call agent.invoke with inputs {:?}. {}", inputs, e)).expect(
-                        "Failed to get agent info"
-                    );
+                    #decode_result
+                }
 
-                    let first_value = match  golem_wasm_rpc::Value::from(result) {
-                        golem_wasm_rpc::Value::Tuple(values) => {
-                            let value = values[0].clone();
-                            value
-                        }
-                        _ => {
-                            panic!("Expected agent.invoke to return a tuple");
-                        }
-                    };
+                // Enqueues the invocation without waiting for the method to run
+                pub fn #trigger_method_name(&self, #(#typed_inputs),*) -> Result<(), golem_agentic::RemoteAgentError> {
+                    let __rpc = golem_wasm_rpc::WasmRpc::new(&self.worker_id);
 
-                    match first_value {
-                        golem_wasm_rpc::Value::Variant{ case_idx, case_value } => {
-                            if case_idx == 2 {
-                                let value: golem_wasm_rpc::Value = case_value.unwrap().as_ref().clone();
-                                let result: #return_type = golem_agentic::FromValue::from_value(value.clone()).expect(
-                                  format!("Failed to convert value {:?} to expected type", value).as_str()
-                                );
-
-                                result
-                            } else {
-                                panic!("Failed to invoke method")
-                            }
-                        }
+                    #build_inputs
 
-                        _ => {
-                            panic!("Expected agent.invoke to return a tuple, but got");
-                        }
-                    }
+                    __rpc.invoke(#wrapped_component_method_name, __inputs.as_slice())
+                        .map_err(golem_agentic::RemoteAgentError::Rpc)
+                }
+
+                // Enqueues the invocation to run at the given time, unless cancelled before
+                pub fn #schedule_method_name(&self, __scheduled_at: golem_agentic::remote::Datetime, #(#typed_inputs),*) -> golem_agentic::remote::ScheduledInvocation {
+                    let __rpc = golem_wasm_rpc::WasmRpc::new(&self.worker_id);
+
+                    #build_inputs
+
+                    let __cancellation_token = __rpc.schedule_cancelable_invocation(
+                        __scheduled_at,
+                        #wrapped_component_method_name,
+                        __inputs.as_slice()
+                    );
+
+                    golem_agentic::remote::ScheduledInvocation::new(#method_name_str_kebab, __scheduled_at, __cancellation_token)
                 }
             })
         } else {
@@ -194,145 +282,89 @@ pub fn agent_definition(_attrs: TokenStream, item: TokenStream) -> TokenStream {
     });
 
     let remote_client = quote! {
+        #[derive(Clone)]
         pub struct #remote_trait_name #impl_generics {
             handle: golem_wasm_rpc::Value,
             worker_id: golem_wasm_rpc::WorkerId,
+            options: golem_agentic::remote::RemoteCallOptions,
         }
 
         impl #remote_trait_name {
-            pub fn new(#(#constructor_params_decl),*) -> Result<Self, String> {
-                let current_component_id_opt = ::golem_agentic::bindings::golem::api::host::get_agent_component(#tr_name_str_kebab);
-                let current_component_id = match current_component_id_opt {
-                    Some(id) => id,
-                    None => return Err(format!("Failed to get current component ID for agent type: {}", #tr_name_str_kebab)),
-                };
-
-                let rpc = golem_wasm_rpc::WasmRpc::ephemeral(current_component_id.clone());
-                let type_name = golem_wasm_rpc::Value::String(#agent_type.type_name.to_string());
-                let type_name_wit_value = &[golem_wasm_rpc::WitValue::from(type_name.clone())];
-
-                 let input_args = vec![
+            pub fn new(#(#constructor_params_decl),*) -> Result<Self, golem_agentic::RemoteAgentError> {
+                let __input_args = vec![
                     #(#constructor_params_wit),*
                 ];
 
-                let agent_handle_in_vec = rpc.invoke_and_await(
-                    "golem:simulated-agentic/simulated-agent.{weather-agent.new}",
-                    input_args.as_slice()
-                ).map_err(|e| format!("Failed to invoke get-agent: {}", e))?;
-
-                let value = golem_wasm_rpc::Value::from(agent_handle_in_vec);
-                match value  {
-                    golem_wasm_rpc::Value::Tuple(values) => {
-                        let handle = values[0].clone();
-                        let handle_wit = golem_wasm_rpc::WitValue::from(handle.clone());
-
-                        let worker_name = match handle.clone() {
-                            golem_wasm_rpc::Value::Handle {uri, ..} => {
-                                let uri = uri.split('/').collect::<Vec<_>>();
-                                uri.get(uri.len() - 1).expect("Worker name not found in URI").clone().to_string()
-                            }
+                let __component_id = ::golem_agentic::bindings::golem::api::host::get_agent_component(#tr_name_str_kebab)
+                    .ok_or_else(|| golem_agentic::RemoteAgentError::Resolution(
+                        format!("no component implements the agent type {}", #tr_name_str_kebab)
+                    ))?;
 
-                            _  => {
-                                panic!("Expected handle to be a tuple, but got: {:?}", handle);
-                            }
-                        };
+                let __rpc = golem_wasm_rpc::WasmRpc::ephemeral(__component_id.clone());
 
-                        Ok(Self { handle: handle.clone(), worker_id: golem_wasm_rpc::WorkerId { component_id: current_component_id, worker_name: worker_name } })
-                    }
-                    _ => {
-                        Err(format!("Expected agent_info to be a tuple, but got: {:?}", value))
-                    }
-                }
+                let (__handle, __worker_name) = golem_agentic::remote::decode_constructed_agent(
+                    __rpc.invoke_and_await(#remote_constructor_name, __input_args.as_slice())
+                )?;
+
+                Ok(Self {
+                    handle: __handle,
+                    worker_id: golem_wasm_rpc::WorkerId { component_id: __component_id, worker_name: __worker_name },
+                    options: golem_agentic::remote::RemoteCallOptions::default(),
+                })
             }
 
             // To be done later
-            pub fn connect_agent(agent_id: &str) -> Result<Self, String> {
-                let agent_id_cloned = agent_id.to_string();
-
+            pub fn connect_agent(agent_id: &str) -> Result<Self, golem_agentic::RemoteAgentError> {
                 let current_component_id = ::golem_agentic::bindings::golem::api::host::get_self_metadata().worker_id.component_id;
-                let agent_info = golem_agentic::agent::parse_agent_id(&agent_id);
 
-                let worker_name = match agent_info {
-                   Ok(agent_info) => agent_info.worker_name,
-                   Err(e) => panic!("Failed to parse agent id: {}", e),
-                };
+                let agent_info = golem_agentic::agent::parse_agent_id(agent_id)
+                    .map_err(golem_agentic::RemoteAgentError::Resolution)?;
 
                 let worker_id = golem_wasm_rpc::WorkerId {
-                   worker_name: worker_name.clone(),
+                   worker_name: agent_info.worker_name,
                    component_id: current_component_id.clone(),
                 };
 
                 let rpc = golem_wasm_rpc::WasmRpc::new(&worker_id);
                 let wit_value: golem_wasm_rpc::WitValue = golem_wasm_rpc::WitValue::from(golem_wasm_rpc::Value::String(agent_id.to_string()));
-                let strings = &[wit_value];
-
-                let agent_info = rpc.invoke_and_await(
-                  "golem:agentic-guest/guest.{get-agent}",
-                  strings
-                ).map_err(|e| format!("Failed to invoke get-agent: {}", e))?;
-
-                let value = golem_wasm_rpc::Value::from(agent_info);
-
-                let handle = match value {
-                   golem_wasm_rpc::Value::Tuple(values) => {
-                     let agent_id = values[0].clone();
-
-                     match agent_id {
-                       golem_wasm_rpc::Value::Record(values) => {
-                           let agent_id =  values[0].clone();
-                           let type_name = values[1].clone();
-                           let handle = values[2].clone();
-                           let u32 = match handle {
-                               golem_wasm_rpc::Value::U32(id) => id as u64,
-                               _ => panic!("Expected handle to be a U32, but got: {:?}", handle),
-                           };
-                           let agent_id = match agent_id {
-                               golem_wasm_rpc::Value::String(id) => id,
-                               _ => panic!("Expected agent_id to be a String, but got: {:?}", agent_id),
-                           };
-
-                           let parsed = golem_agentic::agent::parse_agent_id(&agent_id.to_string()).expect(
-                               format!("Failed to parse agent_id: {}", agent_id).as_str()
-                           );
-
-                           golem_wasm_rpc::Value::Handle {
-                               resource_id: u32,
-                               uri: format!("urn:worker:{}/{}", current_component_id, parsed.worker_name.clone()),
-                           }
-                       }
-
-                       _ => {
-                           panic!("Expected agent_id to be a record, but got: {:?}", agent_id);
-                       }
-                    }
-                  }
 
-                   _ => {
-                      panic!("Expected agent_info to be a tuple, but got: {:?}", value);
-                   }
-               };
+                let handle = golem_agentic::remote::decode_agent_ref(
+                    rpc.invoke_and_await(#remote_get_agent_name, &[wit_value]),
+                    &current_component_id,
+                )?;
 
-                Ok(Self { handle: handle, worker_id: worker_id })
+                Ok(Self {
+                    handle,
+                    worker_id,
+                    options: golem_agentic::remote::RemoteCallOptions::default(),
+                })
             }
 
             pub fn get_container_id(&self) -> golem_wasm_rpc::WorkerId {
                 self.worker_id.clone()
             }
 
+            // A copy of this client calling the methods with the given timeout and retry policy,
+            // either kept as the client or used for a single call
+            pub fn with_options(&self, options: golem_agentic::remote::RemoteCallOptions) -> Self {
+                Self { options, ..self.clone() }
+            }
+
             #(#method_impls)*
         }
     };
 
-    let result = quote! {
+    // Agent methods are always driven by the executor of the agent, so the futures of
+    // `async fn`s do not need to be `Send`
+    quote! {
+        #[allow(async_fn_in_trait)]
         #tr
         #register_fn
         #remote_client
-    };
-
-    result.into()
+    }
 }
 
-fn get_agent_type(tr: &syn::ItemTrait) -> proc_macro2::TokenStream {
+fn get_agent_type(tr: &syn::ItemTrait) -> syn::Result<proc_macro2::TokenStream> {
     let type_name = to_kebab_case(&tr.ident.to_string());
 
     let methods = tr.items.iter().filter_map(|item| {
@@ -364,29 +396,57 @@ fn get_agent_type(tr: &syn::ItemTrait) -> proc_macro2::TokenStream {
 
             let mut parameter_types = vec![]; // This is WIT type for now, but needs to support structured text type
             let mut result_type = vec![];
+            let mut error_type = None;
 
             if let syn::TraitItem::Fn(trait_fn) = item {
-                for input in &trait_fn.sig.inputs {
-                    if let syn::FnArg::Typed(pat_type) = input {
-                        let ty = &pat_type.ty;
-                        parameter_types.push(quote! {
-                            ::golem_agentic::bindings::golem::agent::common::ParameterType::Wit(
+                let typed_inputs = trait_fn.sig.inputs.iter().filter_map(|input| match input {
+                    syn::FnArg::Typed(pat_type) => Some(pat_type),
+                    syn::FnArg::Receiver(_) => None,
+                });
+
+                for pat_type in typed_inputs {
+                    let ty = &pat_type.ty;
+
+                    let param_name = match parameter_name(pat_type) {
+                        Ok(ident) => ident.to_string(),
+                        Err(e) => return Some(Err(e)),
+                    };
+
+                    parameter_types.push(quote! {
+                        ::golem_agentic::bindings::golem::agent::common::NamedParameterType {
+                            name: #param_name.to_string(),
+                            parameter_type: ::golem_agentic::bindings::golem::agent::common::ParameterType::Wit(
                                 <#ty as ::golem_agentic::AgentArg>::get_wit_type()
-                            )
-                        });
-                    }
+                            ),
+                        }
+                    });
                 }
 
                 // Handle return type
                 match &trait_fn.sig.output {
-                    syn::ReturnType::Default => (),
-                    syn::ReturnType::Type(_, ty) => {
-                        result_type.push(quote! {
-                            ::golem_agentic::bindings::golem::agent::common::ParameterType::Wit(
-                                <#ty as ::golem_agentic::AgentArg>::get_wit_type()
-                            )
-                        });
+                    syn::ReturnType::Type(_, ty) if !is_unit_return(&trait_fn.sig.output) => {
+                        // Every chunk of a stream is emitted separately, and only the `T` of a
+                        // `Result<T, E>` is emitted, with `E` described by the error schema
+                        let ty = match result_types(&trait_fn.sig.output) {
+                            Some((ok_type, err_type)) => {
+                                error_type = Some(err_type);
+                                Some(ok_type).filter(|ok_type| !is_unit_type(ok_type))
+                            }
+                            None => Some(stream_item_type(&trait_fn.sig.output).unwrap_or(ty)),
+                        };
+
+                        if let Some(ty) = ty {
+                            result_type.push(quote! {
+                                ::golem_agentic::bindings::golem::agent::common::NamedParameterType {
+                                    name: "return-value".to_string(),
+                                    parameter_type: ::golem_agentic::bindings::golem::agent::common::ParameterType::Wit(
+                                        <#ty as ::golem_agentic::AgentArg>::get_wit_type()
+                                    ),
+                                }
+                            });
+                        }
                     }
+                    _ => (),
                 };
             }
 
@@ -395,8 +455,22 @@ fn get_agent_type(tr: &syn::ItemTrait) -> proc_macro2::TokenStream {
 
             let output_parameters = result_type;
 
+            let error_schema = match error_type {
+                Some(err_type) => quote! {
+                    Some(::golem_agentic::bindings::golem::agent::common::DataSchema::Structured(::golem_agentic::bindings::golem::agent::common::Structured {
+                        parameters: vec![::golem_agentic::bindings::golem::agent::common::NamedParameterType {
+                            name: "error".to_string(),
+                            parameter_type: ::golem_agentic::bindings::golem::agent::common::ParameterType::Wit(
+                                <#err_type as ::golem_agentic::AgentArg>::get_wit_type()
+                            ),
+                        }]
+                    }))
+                },
+                None => quote! { None },
+            };
 
-            Some(quote! {
+
+            Some(Ok(quote! {
                 golem_agentic::bindings::golem::agent::common::AgentMethod {
                     name: #method_name.to_string(),
                     description: #description.to_string(),
@@ -407,20 +481,33 @@ fn get_agent_type(tr: &syn::ItemTrait) -> proc_macro2::TokenStream {
                     output_schema: ::golem_agentic::bindings::golem::agent::common::DataSchema::Structured(::golem_agentic::bindings::golem::agent::common::Structured {
                       parameters: vec![#(#output_parameters),*]
                     }),
+                    error_schema: #error_schema,
                 }
-            })
+            }))
         } else {
             None
         }
-    });
+    }).collect::<syn::Result<Vec<_>>>()?;
 
-    quote! {
+    Ok(quote! {
         golem_agentic::agent_registry::GenericAgentType {
             type_name: #type_name.to_string(),
             description: "".to_string(),
             methods: vec![#(#methods),*],
             requires: vec![]
         }
+    })
+}
+
+// The name of a parameter, which is part of the agent type. Parameters bound to a pattern
+// rather than a name, like `(a, b): (u32, u32)`, have no name and are rejected
+fn parameter_name(pat_type: &syn::PatType) -> syn::Result<&Ident> {
+    match &*pat_type.pat {
+        syn::Pat::Ident(pat_ident) => Ok(&pat_ident.ident),
+        pat => Err(syn::Error::new_spanned(
+            pat,
+            "agent method parameters must be bound to a name rather than a pattern",
+        )),
     }
 }
 
@@ -454,39 +541,99 @@ pub fn agent_implementation(_attrs: TokenStream, item: TokenStream) -> TokenStre
         if let syn::ImplItem::Fn(method) = item {
             let method_name = to_kebab_case(&method.sig.ident.to_string());
 
-            let param_idents: Vec<_> = method
+            let param_idents: Vec<_> = match method
                 .sig
                 .inputs
                 .iter()
-                .filter_map(|arg| {
-                    if let syn::FnArg::Typed(pat_ty) = arg {
-                        if let syn::Pat::Ident(pat_ident) = &*pat_ty.pat {
-                            Some(pat_ident.ident.clone())
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
+                .filter_map(|arg| match arg {
+                    syn::FnArg::Typed(pat_type) => Some(parameter_name(pat_type).cloned()),
+                    syn::FnArg::Receiver(_) => None,
                 })
-                .collect();
+                .collect::<syn::Result<_>>()
+            {
+                Ok(param_idents) => param_idents,
+                Err(e) => return e.to_compile_error().into(),
+            };
+
+            let arity = param_idents.len();
+
+            let arity_check = quote! {
+                if __input.len() != #arity {
+                    return ::golem_agentic::bindings::golem::agent::common::StatusUpdate::Error(
+                        ::golem_agentic::bindings::golem::agent::common::Error::ArityMismatch(
+                            ::golem_agentic::bindings::golem::agent::common::ArityMismatch {
+                                expected: #arity as u32,
+                                actual: __input.len() as u32,
+                            }
+                        )
+                    );
+                }
+            };
 
             let extraction = param_idents.iter().enumerate().map(|(i, ident)| {
                 quote! {
-                 let #ident = ::golem_agentic::AgentArg::from_wit_value(input
-                  .get(#i)
-                  .expect("missing argument")
-                  .clone()).expect("internal error, failed to convert wit value to expected type");
+                 let #ident = match ::golem_agentic::AgentArg::from_wit_value(__input[#i].clone()) {
+                     Ok(__value) => __value,
+                     Err(__error) => {
+                         return ::golem_agentic::bindings::golem::agent::common::StatusUpdate::Error(
+                             ::golem_agentic::bindings::golem::agent::common::Error::TypeMismatch(
+                                 ::golem_agentic::bindings::golem::agent::common::TypeMismatch {
+                                     parameter: stringify!(#ident).to_string(),
+                                     details: __error.to_string(),
+                                 }
+                             )
+                         );
+                     }
+                 };
                 }
             });
 
             let ident = &method.sig.ident;
 
+            // `async fn` methods only complete if their futures wait for pollables or are woken
+            // by each other, see `executor`
+            let method_call = if method.sig.asyncness.is_some() {
+                quote! { ::golem_agentic::executor::block_on(self.#ident(#(#param_idents),*)) }
+            } else {
+                quote! { self.#ident(#(#param_idents),*) }
+            };
+
+            let call = if stream_item_type(&method.sig.output).is_some() {
+                quote! {
+                    ::golem_agentic::stream::open(self.agent_id.clone(), #method_call)
+                }
+            } else if let Some((ok_type, _)) = result_types(&method.sig.output) {
+                let on_success = if is_unit_type(ok_type) {
+                    quote! { ::golem_agentic::bindings::exports::golem::agent::guest::StatusUpdate::Completed }
+                } else {
+                    quote! { ::golem_agentic::bindings::exports::golem::agent::guest::StatusUpdate::Emit(::golem_agentic::ToWitValue::to_wit_value(&__result)) }
+                };
+
+                quote! {
+                    match #method_call {
+                        Ok(__result) => #on_success,
+                        Err(__error) => ::golem_agentic::bindings::exports::golem::agent::guest::StatusUpdate::Error(
+                            ::golem_agentic::bindings::golem::agent::common::Error::ApplicationError(::golem_agentic::ToWitValue::to_wit_value(&__error))
+                        ),
+                    }
+                }
+            } else if is_unit_return(&method.sig.output) {
+                quote! {
+                    #method_call;
+                    ::golem_agentic::bindings::exports::golem::agent::guest::StatusUpdate::Completed
+                }
+            } else {
+                quote! {
+                    let __result = #method_call;
+                    ::golem_agentic::bindings::exports::golem::agent::guest::StatusUpdate::Emit(::golem_agentic::ToWitValue::to_wit_value(&__result))
+                }
+            };
+
             match_arms.push(quote! {
                 #method_name => {
+                    #arity_check
                     #(#extraction)*
-                    let result: String = self.#ident(#(#param_idents),*);
-                    ::golem_agentic::bindings::exports::golem::agent::guest::StatusUpdate::Emit(result.to_string())
+                    #call
                 }
             });
         }
@@ -505,13 +652,15 @@ pub fn agent_implementation(_attrs: TokenStream, item: TokenStream) -> TokenStre
                 self.agent_id.clone()
             }
 
-            fn invoke(&self, method_name: String, input: Vec<golem_wasm_rpc::WitValue>) -> ::golem_agentic::bindings::golem::agent::common::StatusUpdate {
-                match method_name.as_str() {
+            fn invoke(&self, __method_name: String, __input: Vec<golem_wasm_rpc::WitValue>) -> ::golem_agentic::bindings::golem::agent::common::StatusUpdate {
+                match __method_name.as_str() {
                     #(#match_arms,)*
-                    _ =>  ::golem_agentic::bindings::golem::agent::common::StatusUpdate::Emit(format!(
-                        "Method '{}' not found in agent '{}'",
-                        method_name, #trait_name_str
-                    )),
+                    _ =>  ::golem_agentic::bindings::golem::agent::common::StatusUpdate::Error(
+                        ::golem_agentic::bindings::golem::agent::common::Error::UnknownMethod(format!(
+                            "Method '{}' not found in agent '{}'",
+                            __method_name, #trait_name_str
+                        ))
+                    ),
                 }
             }
 
@@ -528,7 +677,7 @@ pub fn agent_implementation(_attrs: TokenStream, item: TokenStream) -> TokenStre
         struct #initiator;
 
         impl golem_agentic::agent_registry::AgentInitiator for #initiator {
-            fn initiate(&self, params: Vec<golem_wasm_rpc::WitValue>) -> golem_agentic::ResolvedAgent {
+            fn initiate(&self, params: Vec<golem_wasm_rpc::WitValue>) -> Result<golem_agentic::ResolvedAgent, golem_agentic::validation::InputValidationError> {
 
                  use golem_agentic::agent::{GetAgentId};
 
@@ -538,7 +687,7 @@ pub fn agent_implementation(_attrs: TokenStream, item: TokenStream) -> TokenStre
                     <#self_ty as ::golem_agentic::AgentConstruct>::construct_from_params(
                         params,
                         agent_id.clone()
-                    )
+                    )?
                 );
 
                  let resolved_agent = golem_agentic::ResolvedAgent {
@@ -558,7 +707,7 @@ pub fn agent_implementation(_attrs: TokenStream, item: TokenStream) -> TokenStre
                     resolved_agent.clone()
                 );
 
-                 resolved_agent
+                 Ok(resolved_agent)
             }
         }
     };
@@ -588,10 +737,11 @@ pub fn agent_implementation(_attrs: TokenStream, item: TokenStream) -> TokenStre
 
             let agent_params = <#self_ty #ty_generics as ::golem_agentic::AgentConstruct>::get_params();
 
-            let agent_params_as_parameter_types = agent_params.iter().map(|(param_name, wit_type)| {
-                ::golem_agentic::bindings::golem::agent::common::ParameterType::Wit(
-                    wit_type.clone()
-                )
+            let agent_params_as_parameter_types = agent_params.into_iter().map(|(param_name, wit_type)| {
+                ::golem_agentic::bindings::golem::agent::common::NamedParameterType {
+                    name: param_name,
+                    parameter_type: ::golem_agentic::bindings::golem::agent::common::ParameterType::Wit(wit_type),
+                }
             }).collect();
 
             let agent_constructor = golem_agentic::bindings::golem::agent::common::AgentConstructor {
@@ -639,104 +789,14 @@ pub fn agent_implementation(_attrs: TokenStream, item: TokenStream) -> TokenStre
     result.into()
 }
 
-#[proc_macro_derive(AgentArg)]
+#[proc_macro_derive(AgentArg, attributes(agent_arg))]
 pub fn derive_agent_arg(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let struct_name = &input.ident;
-
-    let fields = match &input.data {
-        Data::Struct(data_struct) => match &data_struct.fields {
-            Fields::Named(named_fields) => &named_fields.named,
-            _ => panic!("AgentArg can only be derived for structs with named fields"),
-        },
-        _ => panic!("AgentArg can only be derived for structs"),
-    };
 
-    let field_idents_vec: Vec<proc_macro2::Ident> = fields
-        .iter()
-        .map(|f| f.ident.as_ref().unwrap().clone())
-        .collect();
-
-    let field_names: Vec<String> = field_idents_vec
-        .iter()
-        .map(|ident| ident.to_string())
-        .collect();
-    let field_types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
-
-    let to_value_fields: Vec<_> = field_idents_vec
-        .iter()
-        .map(|f| {
-            quote! {
-                golem_agentic::AgentArg::to_value(&self.#f)
-            }
-        })
-        .collect();
-
-    let wit_type_fields: Vec<_> = field_idents_vec.iter().zip(field_types.iter()).map(|(ident, ty)| {
-        let name = ident.to_string();
-        quote! {
-            golem_wasm_ast::analysis::NameTypePair {
-                name: #name.to_string(),
-                typ: golem_wasm_ast::analysis::AnalysedType::from(<#ty as golem_agentic::ToWitType>::get_wit_type()),
-            }
-        }
-    }).collect();
-
-    let from_value_fields: Vec<_> = field_idents_vec
-        .iter()
-        .enumerate()
-        .map(|(i, ident)| {
-            let field_name = &field_names[i];
-            let idx = syn::Index::from(i);
-            quote! {
-                let #ident = golem_agentic::FromValue::from_value(values[#idx].clone())
-                    .map_err(|_| format!("Failed to parse field '{}'", #field_name))?;
-            }
-        })
-        .collect();
-
-    let field_count = field_idents_vec.len();
-
-    let expanded = quote! {
-     impl golem_agentic::ToWitType for #struct_name {
-         fn get_wit_type() -> golem_wasm_rpc::WitType {
-             let analysed_type = golem_wasm_ast::analysis::analysed_type::record(vec![
-                 #(#wit_type_fields),*
-             ]);
-             golem_wasm_rpc::WitType::from(analysed_type)
-         }
-     }
-
-     impl golem_agentic::ToValue for #struct_name {
-         fn to_value(&self) -> golem_wasm_rpc::Value {
-             golem_wasm_rpc::Value::Record(vec![
-                 #(#to_value_fields),*
-             ])
-         }
-     }
-
-     impl golem_agentic::FromWitValue for #struct_name {
-         fn from_wit_value(value: golem_wasm_rpc::WitValue) -> Result<Self, String> {
-             let value = golem_wasm_rpc::Value::from(value);
-             match value {
-                 golem_wasm_rpc::Value::Record(values) => {
-                     if values.len() != #field_count {
-                         return Err(format!("Expected {} fields", #field_count));
-                     }
-
-                     #(#from_value_fields)*
-
-                     Ok(#struct_name {
-                         #(#field_idents_vec),*
-                     })
-                 }
-                 _ => Err("Expected a record WitValue".to_string())
-             }
-         }
-       }
-    };
-
-    TokenStream::from(expanded)
+    match agent_arg::derive_agent_arg_impl(&input) {
+        Ok(expanded) => expanded.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
 }
 
 
@@ -905,7 +965,10 @@ fn build_constructor_code(
         construct_assignments.push(quote! {
             let #name: #ty = <#ty as ::golem_agentic::AgentArg>::from_wit_value(
                 params[#index].clone()
-            ).expect(concat!("AgentConstruct: failed to convert field ", stringify!(#name)));
+            ).map_err(|error| ::golem_agentic::validation::InputValidationError::TypeMismatch {
+                parameter: stringify!(#name).to_string(),
+                error,
+            })?;
         });
 
         construct_fields.push(quote! { #name });
@@ -943,12 +1006,12 @@ fn generate_impls(
             fn construct_from_params(
                 params: Vec<::golem_wasm_rpc::WitValue>,
                 agent_id: String
-            ) -> Self {
+            ) -> Result<Self, ::golem_agentic::validation::InputValidationError> {
                 #(#construct_assignments)*
 
-                Self {
+                Ok(Self {
                     #(#construct_fields),*
-                }
+                })
             }
 
             fn get_params() -> Vec<(String, ::golem_wasm_rpc::WitType)> {
@@ -970,6 +1033,95 @@ fn generate_impls(
     }
 }
 
+// Methods without a return type, or returning `()`, complete without emitting a value
+fn is_unit_return(output: &syn::ReturnType) -> bool {
+    match output {
+        syn::ReturnType::Default => true,
+        syn::ReturnType::Type(_, ty) => is_unit_type(ty),
+    }
+}
+
+fn is_unit_type(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty())
+}
+
+// Methods returning `Result<T, E>` emit `T` on success, and report `E` as an application error
+fn result_types(output: &syn::ReturnType) -> Option<(&syn::Type, &syn::Type)> {
+    match return_type_arguments(output, "Result")?.as_slice() {
+        [ok_type, err_type] => Some((ok_type, err_type)),
+        _ => None,
+    }
+}
+
+// Methods returning `AgentStream<T>` emit every `T` chunk separately
+fn stream_item_type(output: &syn::ReturnType) -> Option<&syn::Type> {
+    match return_type_arguments(output, "AgentStream")?.as_slice() {
+        [item_type] => Some(item_type),
+        _ => None,
+    }
+}
+
+// The generic type arguments of the return type, if it is named `type_name`. As macros only see
+// the syntax, this is based on the last segment of the path, like `Result` in `std::result::Result`
+fn return_type_arguments<'a>(
+    output: &'a syn::ReturnType,
+    type_name: &str,
+) -> Option<Vec<&'a syn::Type>> {
+    let syn::ReturnType::Type(_, ty) = output else {
+        return None;
+    };
+
+    let syn::Type::Path(type_path) = &**ty else {
+        return None;
+    };
+
+    let segment = type_path.path.segments.last()?;
+
+    if segment.ident != type_name {
+        return None;
+    }
+
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+
+    args.args
+        .iter()
+        .map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        })
+        .collect()
+}
+
+// The interface exporting the agents to other components, unless set with
+// `#[agent_definition(interface = "...")]`
+const DEFAULT_REMOTE_INTERFACE: &str = "golem:simulated-agentic/simulated-agent";
+
+// The function constructing an agent of the given type through `interface`,
+// e.g. `golem:simulated-agentic/simulated-agent.{weather-agent.new}`
+fn remote_constructor_name(interface: &str, agent_type_name: &str) -> String {
+    format!("{}.{{{}.new}}", interface, agent_type_name)
+}
+
+// The function resolving an existing agent by its id through `interface`,
+// e.g. `golem:simulated-agentic/simulated-agent.{get-agent}`
+fn remote_get_agent_name(interface: &str) -> String {
+    format!("{}.{{get-agent}}", interface)
+}
+
+// The function invoking a method on an agent of the given type through `interface`,
+// e.g. `golem:simulated-agentic/simulated-agent.{[method]weather-agent.get-weather}`
+fn remote_method_name(interface: &str, agent_type_name: &str, method_name: &str) -> String {
+    format!("{}.{{[method]{}.{}}}", interface, agent_type_name, method_name)
+}
+
+// The function pulling the next chunk of the stream returned by the last invocation on an agent
+// of the given type through `interface`, e.g.
+// `golem:simulated-agentic/simulated-agent.{[method]writer-agent.get-next}`
+fn remote_get_next_name(interface: &str, agent_type_name: &str) -> String {
+    remote_method_name(interface, agent_type_name, "get-next")
+}
 
 fn to_kebab_case(s: &str) -> String {
     let mut result = String::new();
@@ -989,3 +1141,145 @@ fn to_kebab_case(s: &str) -> String {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn expand(attrs: proc_macro2::TokenStream, tr: syn::ItemTrait) -> String {
+        let interface = remote_interface(attrs).unwrap();
+        let constructor_params = vec![(format_ident!("name"), parse_quote!(String))];
+
+        expand_agent_definition(&tr, &interface, &constructor_params).to_string()
+    }
+
+    // The string literals of the expansion, as the targets are passed to `WasmRpc` as literals
+    fn contains_literal(expansion: &str, literal: &str) -> bool {
+        expansion.contains(&format!("{:?}", literal))
+    }
+
+    #[test]
+    fn targets_are_derived_from_the_agent_type() {
+        let expansion = expand(
+            quote! {},
+            parse_quote! {
+                trait WeatherAgent {
+                    fn get_weather(&self, location: String) -> String;
+                    fn reset(&self);
+                }
+            },
+        );
+
+        assert!(contains_literal(
+            &expansion,
+            "golem:simulated-agentic/simulated-agent.{weather-agent.new}"
+        ));
+        assert!(contains_literal(
+            &expansion,
+            "golem:simulated-agentic/simulated-agent.{[method]weather-agent.get-weather}"
+        ));
+        assert!(contains_literal(
+            &expansion,
+            "golem:simulated-agentic/simulated-agent.{[method]weather-agent.reset}"
+        ));
+        assert!(contains_literal(
+            &expansion,
+            "golem:simulated-agentic/simulated-agent.{get-agent}"
+        ));
+        assert!(expansion.contains("pub struct RemoteWeatherAgent"));
+    }
+
+    #[test]
+    fn targets_differ_between_agent_types() {
+        let expansion = expand(
+            quote! {},
+            parse_quote! {
+                trait InventoryTracker {
+                    fn count_items(&self, sku: String) -> u32;
+                }
+            },
+        );
+
+        assert!(contains_literal(
+            &expansion,
+            "golem:simulated-agentic/simulated-agent.{inventory-tracker.new}"
+        ));
+        assert!(contains_literal(
+            &expansion,
+            "golem:simulated-agentic/simulated-agent.{[method]inventory-tracker.count-items}"
+        ));
+        assert!(!expansion.contains("weather-agent"));
+    }
+
+    #[test]
+    fn streams_are_pulled_with_get_next() {
+        let expansion = expand(
+            quote! {},
+            parse_quote! {
+                trait WriterAgent {
+                    fn write(&self, topic: String) -> AgentStream<String>;
+                    fn reset(&self);
+                }
+            },
+        );
+
+        assert!(contains_literal(
+            &expansion,
+            "golem:simulated-agentic/simulated-agent.{[method]writer-agent.get-next}"
+        ));
+
+        let expansion = expand(
+            quote! {},
+            parse_quote! {
+                trait InventoryTracker {
+                    fn count_items(&self, sku: String) -> u32;
+                }
+            },
+        );
+
+        assert!(!expansion.contains("get-next"));
+    }
+
+    #[test]
+    fn interface_can_be_overridden() {
+        let expansion = expand(
+            quote! { interface = "acme:shop/checkout" },
+            parse_quote! {
+                trait CartAgent {
+                    fn add_item(&self, sku: String);
+                }
+            },
+        );
+
+        assert!(contains_literal(&expansion, "acme:shop/checkout.{cart-agent.new}"));
+        assert!(contains_literal(&expansion, "acme:shop/checkout.{get-agent}"));
+        assert!(contains_literal(
+            &expansion,
+            "acme:shop/checkout.{[method]cart-agent.add-item}"
+        ));
+        assert!(!expansion.contains(DEFAULT_REMOTE_INTERFACE));
+    }
+
+    #[test]
+    fn unknown_attributes_are_rejected() {
+        let error = remote_interface(quote! { component = "x" }).unwrap_err();
+
+        assert_eq!(error.to_string(), "expected `interface = \"...\"`");
+    }
+
+    #[test]
+    fn pattern_parameters_are_rejected() {
+        let error = get_agent_type(&parse_quote! {
+            trait GeoAgent {
+                fn distance(&self, (x, y): (f64, f64)) -> f64;
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "agent method parameters must be bound to a name rather than a pattern"
+        );
+    }
+}
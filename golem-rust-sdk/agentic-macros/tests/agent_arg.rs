@@ -0,0 +1,397 @@
+// Skipped fields of enum cases must not be bound by the generated code
+#![deny(unused_variables)]
+
+use golem_agentic::{FromValue, ToValue, ToWitType};
+use golem_agentic_macros::AgentArg;
+use golem_wasm_ast::analysis::analysed_type::{
+    case, f64, field, list, option, r#enum, record, s32, str, tuple, u32, u64, u8, unit_case,
+    variant,
+};
+use golem_wasm_ast::analysis::AnalysedType;
+use golem_wasm_rpc::Value;
+use std::fmt::Debug;
+
+fn round_trip<T: ToValue + FromValue + PartialEq + Debug>(value: T) {
+    assert_eq!(T::from_value(value.to_value()).unwrap(), value);
+}
+
+fn wit_type<T: ToWitType>() -> AnalysedType {
+    AnalysedType::from(T::get_wit_type())
+}
+
+#[derive(AgentArg, Debug, PartialEq)]
+struct Location {
+    city: String,
+    zip: u32,
+}
+
+#[derive(AgentArg, Debug, PartialEq)]
+struct CustomerId(String);
+
+#[derive(AgentArg, Debug, PartialEq)]
+struct Point(i32, i32, f64);
+
+#[derive(AgentArg, Debug, PartialEq)]
+struct Page<T> {
+    items: Vec<T>,
+    next: Option<String>,
+}
+
+#[derive(AgentArg, Debug, PartialEq)]
+enum Unit {
+    Celsius,
+    Fahrenheit,
+    #[agent_arg(rename = "k")]
+    Kelvin,
+}
+
+#[derive(AgentArg, Debug, PartialEq)]
+enum Command {
+    Stop,
+    Say(String),
+    Move(i32, i32),
+    Search { query: String, limit: u32 },
+}
+
+#[derive(AgentArg, Debug, PartialEq)]
+enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+fn default_limit() -> u32 {
+    10
+}
+
+#[derive(AgentArg, Debug, PartialEq)]
+struct Audit {
+    created_by: String,
+    created_at: u64,
+}
+
+#[derive(AgentArg, Debug, PartialEq)]
+struct Order {
+    #[agent_arg(rename = "id")]
+    order_id: String,
+    #[agent_arg(skip)]
+    cache: Option<String>,
+    #[agent_arg(flatten)]
+    audit: Audit,
+    #[agent_arg(default)]
+    note: Option<String>,
+    #[agent_arg(default = "default_limit")]
+    limit: u32,
+}
+
+#[derive(AgentArg, Debug, PartialEq)]
+enum Event {
+    Created {
+        #[agent_arg(rename = "id")]
+        order_id: String,
+        #[agent_arg(skip)]
+        cache: Option<String>,
+    },
+    #[agent_arg(rename = "removed")]
+    Deleted,
+}
+
+fn order() -> Order {
+    Order {
+        order_id: "o1".to_string(),
+        cache: None,
+        audit: Audit {
+            created_by: "me".to_string(),
+            created_at: 3,
+        },
+        note: Some("fragile".to_string()),
+        limit: 5,
+    }
+}
+
+#[test]
+fn field_names_are_kebab_cased_unless_renamed() {
+    assert_eq!(
+        wit_type::<Order>(),
+        record(vec![
+            field("id", str()),
+            field("created-by", str()),
+            field("created-at", u64()),
+            field("note", option(str())),
+            field("limit", u32()),
+        ])
+    );
+}
+
+#[test]
+fn case_names_are_kebab_cased_unless_renamed() {
+    assert_eq!(
+        wit_type::<Event>(),
+        variant(vec![
+            case("created", record(vec![field("id", str())])),
+            unit_case("removed"),
+        ])
+    );
+}
+
+#[test]
+fn skipped_fields_are_defaulted() {
+    let mut order = order();
+    order.cache = Some("cached".to_string());
+
+    let decoded = Order::from_value(order.to_value()).unwrap();
+    assert_eq!(decoded.cache, None);
+
+    let event = Event::Created {
+        order_id: "o1".to_string(),
+        cache: Some("cached".to_string()),
+    };
+
+    assert_eq!(
+        event.to_value(),
+        Value::Variant {
+            case_idx: 0,
+            case_value: Some(Box::new(Value::Record(vec![Value::String(
+                "o1".to_string()
+            )]))),
+        }
+    );
+    assert_eq!(
+        Event::from_value(event.to_value()).unwrap(),
+        Event::Created {
+            order_id: "o1".to_string(),
+            cache: None,
+        }
+    );
+}
+
+#[test]
+fn missing_trailing_fields_are_defaulted() {
+    let value = Value::Record(vec![
+        Value::String("o1".to_string()),
+        Value::String("me".to_string()),
+        Value::U64(3),
+    ]);
+
+    let decoded = Order::from_value(value).unwrap();
+    assert_eq!(decoded.note, None);
+    assert_eq!(decoded.limit, 10);
+}
+
+#[test]
+fn missing_fields_without_default_are_rejected() {
+    let value = Value::Record(vec![Value::String("o1".to_string())]);
+
+    assert_eq!(
+        Order::from_value(value).unwrap_err().to_string(),
+        "created-by: missing field"
+    );
+}
+
+#[test]
+fn flattened_fields_are_inlined() {
+    assert_eq!(
+        order().to_value(),
+        Value::Record(vec![
+            Value::String("o1".to_string()),
+            Value::String("me".to_string()),
+            Value::U64(3),
+            Value::Option(Some(Box::new(Value::String("fragile".to_string())))),
+            Value::U32(5),
+        ])
+    );
+
+    round_trip(order());
+    round_trip(Event::Deleted);
+}
+
+#[test]
+fn records_round_trip() {
+    assert_eq!(
+        wit_type::<Location>(),
+        record(vec![field("city", str()), field("zip", u32())])
+    );
+
+    round_trip(Location {
+        city: "Berlin".to_string(),
+        zip: 10115,
+    });
+}
+
+#[test]
+fn generic_records_round_trip() {
+    assert_eq!(
+        wit_type::<Page<u32>>(),
+        record(vec![
+            field("items", list(u32())),
+            field("next", option(str()))
+        ])
+    );
+
+    round_trip(Page {
+        items: vec![Location {
+            city: "Berlin".to_string(),
+            zip: 10115,
+        }],
+        next: Some("cursor".to_string()),
+    });
+}
+
+#[test]
+fn records_with_wrong_fields_are_rejected() {
+    let value = Value::Record(vec![Value::String("Berlin".to_string()), Value::Bool(true)]);
+    assert_eq!(
+        Location::from_value(value).unwrap_err().to_string(),
+        "zip: expected u32, but found bool"
+    );
+
+    let value = Value::Record(vec![
+        Value::String("Berlin".to_string()),
+        Value::U32(10115),
+        Value::U32(1),
+    ]);
+    assert_eq!(
+        Location::from_value(value).unwrap_err().to_string(),
+        "record has 1 more fields than expected"
+    );
+}
+
+#[test]
+fn newtypes_are_transparent() {
+    assert_eq!(wit_type::<CustomerId>(), str());
+    assert_eq!(
+        CustomerId("c1".to_string()).to_value(),
+        Value::String("c1".to_string())
+    );
+
+    round_trip(CustomerId("c1".to_string()));
+}
+
+#[test]
+fn tuple_structs_are_tuples() {
+    assert_eq!(wit_type::<Point>(), tuple(vec![s32(), s32(), f64()]));
+    assert_eq!(
+        Point(1, 2, 0.5).to_value(),
+        Value::Tuple(vec![Value::S32(1), Value::S32(2), Value::F64(0.5)])
+    );
+
+    round_trip(Point(1, -2, 0.5));
+}
+
+#[test]
+fn tuple_structs_with_wrong_items_are_rejected() {
+    let value = Value::Tuple(vec![Value::S32(1), Value::S32(2)]);
+    assert_eq!(
+        Point::from_value(value).unwrap_err().to_string(),
+        "expected 3 elements, but found 2"
+    );
+
+    let value = Value::Tuple(vec![Value::S32(1), Value::U8(2), Value::F64(0.5)]);
+    assert_eq!(
+        Point::from_value(value).unwrap_err().to_string(),
+        "[1]: expected s32, but found u8"
+    );
+}
+
+#[test]
+fn unit_only_enums_are_enums() {
+    assert_eq!(wit_type::<Unit>(), r#enum(&["celsius", "fahrenheit", "k"]));
+    assert_eq!(Unit::Fahrenheit.to_value(), Value::Enum(1));
+
+    round_trip(Unit::Celsius);
+    round_trip(Unit::Kelvin);
+}
+
+#[test]
+fn unknown_enum_cases_are_rejected() {
+    assert_eq!(
+        Unit::from_value(Value::Enum(3)).unwrap_err().to_string(),
+        "unknown case 3 of Unit"
+    );
+    assert_eq!(
+        Unit::from_value(Value::U32(0)).unwrap_err().to_string(),
+        "expected enum, but found u32"
+    );
+}
+
+#[test]
+fn enums_with_payloads_are_variants() {
+    assert_eq!(
+        wit_type::<Command>(),
+        variant(vec![
+            unit_case("stop"),
+            case("say", str()),
+            case("move", tuple(vec![s32(), s32()])),
+            case(
+                "search",
+                record(vec![field("query", str()), field("limit", u32())])
+            ),
+        ])
+    );
+    assert_eq!(
+        Command::Move(1, 2).to_value(),
+        Value::Variant {
+            case_idx: 2,
+            case_value: Some(Box::new(Value::Tuple(vec![Value::S32(1), Value::S32(2)]))),
+        }
+    );
+
+    round_trip(Command::Stop);
+    round_trip(Command::Say("hello".to_string()));
+    round_trip(Command::Move(1, -2));
+    round_trip(Command::Search {
+        query: "shoes".to_string(),
+        limit: 3,
+    });
+}
+
+#[test]
+fn generic_variants_round_trip() {
+    assert_eq!(
+        wit_type::<Either<u8, String>>(),
+        variant(vec![case("left", u8()), case("right", str())])
+    );
+
+    round_trip(Either::<u8, String>::Left(1));
+    round_trip(Either::<u8, String>::Right("right".to_string()));
+}
+
+#[test]
+fn variants_with_wrong_payloads_are_rejected() {
+    let value = Value::Variant {
+        case_idx: 4,
+        case_value: None,
+    };
+    assert_eq!(
+        Command::from_value(value).unwrap_err().to_string(),
+        "unknown case 4 of Command"
+    );
+
+    let value = Value::Variant {
+        case_idx: 1,
+        case_value: None,
+    };
+    assert_eq!(
+        Command::from_value(value).unwrap_err().to_string(),
+        "unexpected payload for case 1 of Command"
+    );
+
+    let value = Value::Variant {
+        case_idx: 2,
+        case_value: Some(Box::new(Value::Tuple(vec![Value::S32(1)]))),
+    };
+    assert_eq!(
+        Command::from_value(value).unwrap_err().to_string(),
+        "move: expected 2 elements, but found 1"
+    );
+
+    let value = Value::Variant {
+        case_idx: 3,
+        case_value: Some(Box::new(Value::Record(vec![Value::String(
+            "shoes".to_string(),
+        )]))),
+    };
+    assert_eq!(
+        Command::from_value(value).unwrap_err().to_string(),
+        "search.limit: missing field"
+    );
+}
@@ -1,7 +1,11 @@
+use crate::validation::InputValidationError;
 use golem_wasm_rpc::WitType;
 
 pub trait AgentConstruct: Sized {
-    fn construct_from_params(params: Vec<golem_wasm_rpc::WitValue>, agent_id: String) -> Self;
+    fn construct_from_params(
+        params: Vec<golem_wasm_rpc::WitValue>,
+        agent_id: String,
+    ) -> Result<Self, InputValidationError>;
     fn get_params() -> Vec<(String, WitType)>;
     fn get_agent_dependencies() -> Vec<String>;
 }
@@ -1,5 +1,6 @@
 use crate::agent_instance_registry::AgentName;
 use crate::bindings::exports::golem::agent::guest::{AgentRef, AgentType, WitValue};
+use crate::validation::InputValidationError;
 use crate::ResolvedAgent;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
@@ -213,5 +214,6 @@ pub fn get_agent_initiator(
 }
 
 pub trait AgentInitiator: Send + Sync {
-    fn initiate(&self, params: Vec<WitValue>) -> ResolvedAgent;
+    // Fails if the parameters cannot be decoded into the constructor parameters of the agent
+    fn initiate(&self, params: Vec<WitValue>) -> Result<ResolvedAgent, InputValidationError>;
 }
@@ -0,0 +1,161 @@
+use golem_wasm_ast::analysis::AnalysedType;
+use golem_wasm_rpc::Value;
+use std::fmt::{Display, Formatter};
+
+// An error decoding a `Value` (or `WitValue`) into a Rust type.
+// The path points to the part of the (possibly deeply nested) value which failed to decode,
+// e.g. `order.items[3].price`, where `order` is usually the name of the method parameter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConversionError {
+    pub path: Vec<PathSegment>,
+    pub kind: ConversionErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathSegment {
+    Field(String),
+    Index(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ConversionErrorKind {
+    #[error("expected {expected}, but found {actual}")]
+    TypeMismatch { expected: String, actual: String },
+    #[error("expected {expected} elements, but found {actual}")]
+    LengthMismatch { expected: usize, actual: usize },
+    #[error("missing field")]
+    MissingField,
+    #[error("unknown case {case_idx} of {type_name}")]
+    UnknownCase { type_name: String, case_idx: u32 },
+    #[error("{0}")]
+    Custom(String),
+}
+
+impl ConversionError {
+    pub fn new(kind: ConversionErrorKind) -> Self {
+        ConversionError { path: vec![], kind }
+    }
+
+    pub fn type_mismatch(expected: impl Into<String>, actual: &Value) -> Self {
+        Self::new(ConversionErrorKind::TypeMismatch {
+            expected: expected.into(),
+            actual: value_kind(actual).to_string(),
+        })
+    }
+
+    pub fn length_mismatch(expected: usize, actual: usize) -> Self {
+        Self::new(ConversionErrorKind::LengthMismatch { expected, actual })
+    }
+
+    pub fn missing_field(name: impl Into<String>) -> Self {
+        Self::new(ConversionErrorKind::MissingField).at_field(name)
+    }
+
+    pub fn unknown_case(type_name: impl Into<String>, case_idx: u32) -> Self {
+        Self::new(ConversionErrorKind::UnknownCase {
+            type_name: type_name.into(),
+            case_idx,
+        })
+    }
+
+    pub fn custom(message: impl Into<String>) -> Self {
+        Self::new(ConversionErrorKind::Custom(message.into()))
+    }
+
+    // Errors are created at the innermost value, and the path is built up
+    // while they bubble up through the enclosing values
+    pub fn at_field(mut self, name: impl Into<String>) -> Self {
+        self.path.insert(0, PathSegment::Field(name.into()));
+        self
+    }
+
+    pub fn at_index(mut self, idx: usize) -> Self {
+        self.path.insert(0, PathSegment::Index(idx));
+        self
+    }
+
+    pub fn path_string(&self) -> String {
+        let mut result = String::new();
+
+        for segment in &self.path {
+            match segment {
+                PathSegment::Field(name) if result.is_empty() => result.push_str(name),
+                PathSegment::Field(name) => {
+                    result.push('.');
+                    result.push_str(name);
+                }
+                PathSegment::Index(idx) => result.push_str(&format!("[{}]", idx)),
+            }
+        }
+
+        result
+    }
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.kind)
+        } else {
+            write!(f, "{}: {}", self.path_string(), self.kind)
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+// Name of the kind of a value, in WIT terms
+pub fn value_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Bool(_) => "bool",
+        Value::U8(_) => "u8",
+        Value::U16(_) => "u16",
+        Value::U32(_) => "u32",
+        Value::U64(_) => "u64",
+        Value::S8(_) => "s8",
+        Value::S16(_) => "s16",
+        Value::S32(_) => "s32",
+        Value::S64(_) => "s64",
+        Value::F32(_) => "f32",
+        Value::F64(_) => "f64",
+        Value::Char(_) => "char",
+        Value::String(_) => "string",
+        Value::List(_) => "list",
+        Value::Tuple(_) => "tuple",
+        Value::Record(_) => "record",
+        Value::Variant { .. } => "variant",
+        Value::Enum(_) => "enum",
+        Value::Flags(_) => "flags",
+        Value::Option(_) => "option",
+        Value::Result(_) => "result",
+        Value::Handle { .. } => "handle",
+    }
+}
+
+// Name of the kind of a type, in the same terms as `value_kind`
+pub fn wit_type_name(typ: &AnalysedType) -> &'static str {
+    match typ {
+        AnalysedType::Bool(_) => "bool",
+        AnalysedType::U8(_) => "u8",
+        AnalysedType::U16(_) => "u16",
+        AnalysedType::U32(_) => "u32",
+        AnalysedType::U64(_) => "u64",
+        AnalysedType::S8(_) => "s8",
+        AnalysedType::S16(_) => "s16",
+        AnalysedType::S32(_) => "s32",
+        AnalysedType::S64(_) => "s64",
+        AnalysedType::F32(_) => "f32",
+        AnalysedType::F64(_) => "f64",
+        AnalysedType::Chr(_) => "char",
+        AnalysedType::Str(_) => "string",
+        AnalysedType::List(_) => "list",
+        AnalysedType::Tuple(_) => "tuple",
+        AnalysedType::Record(_) => "record",
+        AnalysedType::Variant(_) => "variant",
+        AnalysedType::Enum(_) => "enum",
+        AnalysedType::Flags(_) => "flags",
+        AnalysedType::Option(_) => "option",
+        AnalysedType::Result(_) => "result",
+        AnalysedType::Handle(_) => "handle",
+    }
+}
@@ -0,0 +1,274 @@
+// A minimal single-threaded executor driving `async` agent methods to completion.
+//
+// Components can only block on `wasi:io/poll`, so futures which cannot make progress yet
+// register the pollables they are waiting for (using `wait_for`). Once the future is pending,
+// `block_on` waits until at least one of the registered pollables is ready, and polls it again.
+// As every pending future registers its pollables on each poll, this allows awaiting several
+// outgoing HTTP or RPC calls concurrently, for example by awaiting them with `join_all`.
+//
+// Futures may also wait through their `Waker`, as long as they are woken while `block_on` polls
+// the future, for example by another future of the same method sending to a channel, and joined
+// with `join_all`. Nothing else can wake them, as components run on a single thread: futures
+// waiting for the reactor of another async runtime (like the ones of `tokio` or `wstd`) are not
+// supported. `block_on` panics once the future can no longer make progress.
+
+use golem_wasm_rpc::wasi::io::poll::{poll, Pollable};
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+thread_local! {
+    // The pollables the futures polled by `block_on` are waiting for
+    static WAITING: RefCell<Vec<Rc<Pollable>>> = const { RefCell::new(Vec::new()) };
+}
+
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let woken = Arc::new(Woken(AtomicBool::new(false)));
+    let waker = Waker::from(woken.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    // Futures awaited by an outer `block_on` keep waiting for their own pollables
+    let outer_waiting = WAITING.with(|waiting| waiting.take());
+
+    let result = loop {
+        if let Poll::Ready(result) = future.as_mut().poll(&mut cx) {
+            break result;
+        }
+
+        let waiting = WAITING.with(|waiting| waiting.take());
+
+        if woken.0.swap(false, Ordering::SeqCst) {
+            continue;
+        }
+
+        if waiting.is_empty() {
+            panic!(
+                "The future can never complete: it is pending, but it was not woken and is not \
+                 waiting for any pollable registered with `executor::wait_for`. Futures driven by \
+                 another async runtime are not supported"
+            );
+        }
+
+        let pollables: Vec<&Pollable> = waiting.iter().map(|pollable| pollable.as_ref()).collect();
+        poll(&pollables);
+    };
+
+    WAITING.with(|waiting| *waiting.borrow_mut() = outer_waiting);
+    result
+}
+
+// Completes once the pollable is ready
+pub fn wait_for(pollable: Pollable) -> WaitFor {
+    wait_for_any(vec![pollable])
+}
+
+// Completes once any of the pollables is ready
+pub fn wait_for_any(pollables: Vec<Pollable>) -> WaitFor {
+    WaitFor {
+        pollables: pollables.into_iter().map(Rc::new).collect(),
+    }
+}
+
+pub struct WaitFor {
+    pollables: Vec<Rc<Pollable>>,
+}
+
+impl Future for WaitFor {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        if self.pollables.iter().any(|pollable| pollable.ready()) {
+            Poll::Ready(())
+        } else {
+            WAITING.with(|waiting| waiting.borrow_mut().extend(self.pollables.iter().cloned()));
+            Poll::Pending
+        }
+    }
+}
+
+// Completes once all the futures completed, with their outputs in the same order
+pub fn join_all<F: Future>(futures: impl IntoIterator<Item = F>) -> JoinAll<F> {
+    JoinAll {
+        futures: futures
+            .into_iter()
+            .map(|future| MaybeDone::Pending(Box::pin(future)))
+            .collect(),
+    }
+}
+
+pub struct JoinAll<F: Future> {
+    futures: Vec<MaybeDone<F>>,
+}
+
+// The futures are pinned in their own boxes, and the outputs are never pinned
+impl<F: Future> Unpin for JoinAll<F> {}
+
+enum MaybeDone<F: Future> {
+    Pending(Pin<Box<F>>),
+    Done(Option<F::Output>),
+}
+
+impl<F: Future> Future for JoinAll<F> {
+    type Output = Vec<F::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Vec<F::Output>> {
+        let this = self.get_mut();
+        let mut all_done = true;
+
+        for maybe_done in this.futures.iter_mut() {
+            if let MaybeDone::Pending(future) = maybe_done {
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(output) => *maybe_done = MaybeDone::Done(Some(output)),
+                    Poll::Pending => all_done = false,
+                }
+            }
+        }
+
+        if !all_done {
+            return Poll::Pending;
+        }
+
+        let outputs = this
+            .futures
+            .iter_mut()
+            .map(|maybe_done| match maybe_done {
+                MaybeDone::Done(output) => output.take().expect("JoinAll polled after completion"),
+                MaybeDone::Pending(_) => unreachable!(),
+            })
+            .collect();
+
+        Poll::Ready(outputs)
+    }
+}
+
+struct Woken(AtomicBool);
+
+impl Wake for Woken {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // Pending until polled `times` times, waking itself on each poll like `yield_now`
+    struct Yield {
+        times: usize,
+        polls: Rc<Cell<usize>>,
+    }
+
+    impl Future for Yield {
+        type Output = usize;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<usize> {
+            let polls = self.polls.get() + 1;
+            self.polls.set(polls);
+
+            if polls > self.times {
+                Poll::Ready(polls)
+            } else {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    fn yield_times(times: usize) -> Yield {
+        Yield {
+            times,
+            polls: Rc::new(Cell::new(0)),
+        }
+    }
+
+    // A single value channel, whose receiver waits through its waker
+    #[derive(Clone, Default)]
+    struct Channel {
+        value: Rc<Cell<Option<u32>>>,
+        waker: Rc<RefCell<Option<Waker>>>,
+    }
+
+    impl Channel {
+        fn send(&self, value: u32) {
+            self.value.set(Some(value));
+
+            if let Some(waker) = self.waker.borrow_mut().take() {
+                waker.wake();
+            }
+        }
+    }
+
+    impl Future for Channel {
+        type Output = u32;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> {
+            match self.value.take() {
+                Some(value) => Poll::Ready(value),
+                None => {
+                    *self.waker.borrow_mut() = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ready_futures_complete() {
+        assert_eq!(block_on(async { 1 + 2 }), 3);
+    }
+
+    #[test]
+    fn woken_futures_are_polled_again() {
+        assert_eq!(block_on(yield_times(3)), 4);
+    }
+
+    #[test]
+    fn futures_woken_by_other_futures_complete() {
+        let channel = Channel::default();
+        let sender = channel.clone();
+
+        let futures: Vec<Pin<Box<dyn Future<Output = Option<u32>>>>> = vec![
+            Box::pin(async move { Some(channel.await) }),
+            Box::pin(async move {
+                yield_times(2).await;
+                sender.send(7);
+                None
+            }),
+        ];
+
+        assert_eq!(block_on(join_all(futures)), vec![Some(7), None]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The future can never complete")]
+    fn futures_which_are_never_woken_are_rejected() {
+        block_on(Channel::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "The future can never complete")]
+    fn waiting_for_no_pollables_never_completes() {
+        block_on(wait_for_any(vec![]));
+    }
+
+    #[test]
+    fn join_all_keeps_the_order_of_the_futures() {
+        let futures = [3, 0, 1].map(yield_times);
+
+        assert_eq!(block_on(join_all(futures)), vec![4, 1, 2]);
+    }
+
+    #[test]
+    fn join_all_of_no_futures_is_empty() {
+        let futures: Vec<Yield> = vec![];
+
+        assert!(block_on(join_all(futures)).is_empty());
+    }
+}
@@ -0,0 +1,683 @@
+// Schema-guided conversion between JSON and WIT values, used to call agents from sources which
+// only speak JSON, such as LLM tool calls and MCP requests.
+//
+// The JSON representation of a value depends on its WIT type:
+//   - `bool`, integers and floats map to JSON booleans and numbers, `char` and `string` to strings
+//   - `list<t>` maps to an array, and `tuple<..>` to an array of the same length
+//   - records map to objects, where a missing field is only accepted if its type is an `option`
+//   - variants map to `{"case-name": payload}`, or just `"case-name"` for cases without payload
+//   - enums map to the name of the case
+//   - `option<t>` maps to `null` or the value itself
+//   - `result<t, e>` maps to `{"ok": value}` or `{"err": value}`
+//   - flags map to an array with the names of the flags which are set
+//   - resource handles are not supported
+
+use crate::bindings::golem::agent::common::{AgentMethod, DataSchema, ParameterType};
+use crate::conversion_error::{wit_type_name, ConversionError, ConversionErrorKind};
+use golem_wasm_ast::analysis::AnalysedType;
+use golem_wasm_rpc::{Value, WitType, WitValue};
+use serde_json::{Map, Number, Value as JsonValue};
+
+// Parses the JSON arguments of a method call, given either as an array with one element per
+// parameter, or as an object keyed by the parameter names
+pub fn method_input_from_json(
+    method: &AgentMethod,
+    json: &JsonValue,
+) -> Result<Vec<WitValue>, ConversionError> {
+    parameters_from_json(&method.input_schema, json)
+}
+
+// Renders the result of a method call, which is a single value described by the output schema
+pub fn method_output_to_json(
+    method: &AgentMethod,
+    value: WitValue,
+) -> Result<JsonValue, ConversionError> {
+    single_value_to_json(&method.output_schema, value)
+}
+
+// Renders the application error a method call failed with, described by the error schema
+pub fn method_error_to_json(
+    method: &AgentMethod,
+    value: WitValue,
+) -> Result<JsonValue, ConversionError> {
+    match &method.error_schema {
+        Some(schema) => single_value_to_json(schema, value),
+        None => Err(ConversionError::custom(format!(
+            "method '{}' has no application errors",
+            method.name
+        ))),
+    }
+}
+
+fn single_value_to_json(
+    schema: &DataSchema,
+    value: WitValue,
+) -> Result<JsonValue, ConversionError> {
+    match schema {
+        DataSchema::Structured(structured) => match structured.parameters.as_slice() {
+            [] => Ok(JsonValue::Null),
+            [parameter] => parameter_to_json(&parameter.parameter_type, Value::from(value)),
+            parameters => Err(ConversionError::custom(format!(
+                "expected a single output parameter, but the schema has {}",
+                parameters.len()
+            ))),
+        },
+        DataSchema::Multimodal(_) => Err(ConversionError::custom(
+            "multimodal schemas are not supported",
+        )),
+    }
+}
+
+pub fn parameters_from_json(
+    schema: &DataSchema,
+    json: &JsonValue,
+) -> Result<Vec<WitValue>, ConversionError> {
+    let parameters = match schema {
+        DataSchema::Structured(structured) => &structured.parameters,
+        DataSchema::Multimodal(_) => {
+            return Err(ConversionError::custom(
+                "multimodal schemas are not supported",
+            ))
+        }
+    };
+
+    let names: Vec<&String> = parameters.iter().map(|p| &p.name).collect();
+
+    match json {
+        JsonValue::Array(items) => {
+            if items.len() != parameters.len() {
+                return Err(ConversionError::length_mismatch(
+                    parameters.len(),
+                    items.len(),
+                ));
+            }
+
+            parameters
+                .iter()
+                .zip(items)
+                .map(|(parameter, item)| {
+                    parameter_from_json(&parameter.parameter_type, item)
+                        .map(WitValue::from)
+                        .map_err(|e| e.at_field(parameter.name.clone()))
+                })
+                .collect()
+        }
+        JsonValue::Object(object) => {
+            if let Some(unknown) = object.keys().find(|key| !names.contains(key)) {
+                return Err(ConversionError::custom("unknown parameter").at_field(unknown.clone()));
+            }
+
+            parameters
+                .iter()
+                .map(|parameter| match object.get(&parameter.name) {
+                    Some(item) => parameter_from_json(&parameter.parameter_type, item)
+                        .map(WitValue::from)
+                        .map_err(|e| e.at_field(parameter.name.clone())),
+                    None => match &parameter.parameter_type {
+                        ParameterType::Wit(wit_type)
+                            if matches!(
+                                AnalysedType::from(wit_type.clone()),
+                                AnalysedType::Option(_)
+                            ) =>
+                        {
+                            Ok(WitValue::from(Value::Option(None)))
+                        }
+                        _ => Err(ConversionError::missing_field(parameter.name.clone())),
+                    },
+                })
+                .collect()
+        }
+        other => Err(type_mismatch("array or object of parameters", other)),
+    }
+}
+
+fn parameter_from_json(
+    parameter: &ParameterType,
+    json: &JsonValue,
+) -> Result<Value, ConversionError> {
+    match parameter {
+        ParameterType::Wit(wit_type) => json_to_value(json, &AnalysedType::from(wit_type.clone())),
+        ParameterType::Text(_) => match json {
+            JsonValue::String(text) => Ok(Value::String(text.clone())),
+            other => Err(type_mismatch("string", other)),
+        },
+    }
+}
+
+fn parameter_to_json(
+    parameter: &ParameterType,
+    value: Value,
+) -> Result<JsonValue, ConversionError> {
+    match parameter {
+        ParameterType::Wit(wit_type) => {
+            value_to_json(&value, &AnalysedType::from(wit_type.clone()))
+        }
+        ParameterType::Text(_) => match value {
+            Value::String(text) => Ok(JsonValue::String(text)),
+            other => Err(ConversionError::type_mismatch("string", &other)),
+        },
+    }
+}
+
+pub fn json_to_wit_value(json: &JsonValue, typ: &WitType) -> Result<WitValue, ConversionError> {
+    json_to_value(json, &AnalysedType::from(typ.clone())).map(WitValue::from)
+}
+
+pub fn wit_value_to_json(value: WitValue, typ: &WitType) -> Result<JsonValue, ConversionError> {
+    value_to_json(&Value::from(value), &AnalysedType::from(typ.clone()))
+}
+
+pub fn json_to_value(json: &JsonValue, typ: &AnalysedType) -> Result<Value, ConversionError> {
+    match typ {
+        AnalysedType::Bool(_) => match json {
+            JsonValue::Bool(b) => Ok(Value::Bool(*b)),
+            other => Err(type_mismatch("bool", other)),
+        },
+        AnalysedType::U8(_) => integer(json, "u8").map(Value::U8),
+        AnalysedType::U16(_) => integer(json, "u16").map(Value::U16),
+        AnalysedType::U32(_) => integer(json, "u32").map(Value::U32),
+        AnalysedType::U64(_) => integer(json, "u64").map(Value::U64),
+        AnalysedType::S8(_) => integer(json, "s8").map(Value::S8),
+        AnalysedType::S16(_) => integer(json, "s16").map(Value::S16),
+        AnalysedType::S32(_) => integer(json, "s32").map(Value::S32),
+        AnalysedType::S64(_) => integer(json, "s64").map(Value::S64),
+        AnalysedType::F32(_) => float(json, "f32").map(|f| Value::F32(f as f32)),
+        AnalysedType::F64(_) => float(json, "f64").map(Value::F64),
+        AnalysedType::Chr(_) => match json {
+            JsonValue::String(s) => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(Value::Char(c)),
+                    _ => Err(ConversionError::custom(format!(
+                        "expected a single character, but found \"{}\"",
+                        s
+                    ))),
+                }
+            }
+            other => Err(type_mismatch("char", other)),
+        },
+        AnalysedType::Str(_) => match json {
+            JsonValue::String(s) => Ok(Value::String(s.clone())),
+            other => Err(type_mismatch("string", other)),
+        },
+        AnalysedType::List(list) => match json {
+            JsonValue::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(idx, item)| json_to_value(item, &list.inner).map_err(|e| e.at_index(idx)))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::List),
+            other => Err(type_mismatch("list", other)),
+        },
+        AnalysedType::Tuple(tuple) => match json {
+            JsonValue::Array(items) if items.len() == tuple.items.len() => items
+                .iter()
+                .zip(&tuple.items)
+                .enumerate()
+                .map(|(idx, (item, typ))| json_to_value(item, typ).map_err(|e| e.at_index(idx)))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Tuple),
+            JsonValue::Array(items) => Err(ConversionError::length_mismatch(
+                tuple.items.len(),
+                items.len(),
+            )),
+            other => Err(type_mismatch("tuple", other)),
+        },
+        AnalysedType::Record(record) => {
+            let object = match json {
+                JsonValue::Object(object) => object,
+                other => return Err(type_mismatch("record", other)),
+            };
+
+            if let Some(unknown) = object
+                .keys()
+                .find(|key| !record.fields.iter().any(|field| &field.name == *key))
+            {
+                return Err(ConversionError::custom("unknown field").at_field(unknown.clone()));
+            }
+
+            record
+                .fields
+                .iter()
+                .map(|field| match object.get(&field.name) {
+                    Some(value) => {
+                        json_to_value(value, &field.typ).map_err(|e| e.at_field(field.name.clone()))
+                    }
+                    None if matches!(field.typ, AnalysedType::Option(_)) => Ok(Value::Option(None)),
+                    None => Err(ConversionError::missing_field(field.name.clone())),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Record)
+        }
+        AnalysedType::Variant(variant) => {
+            let (case_name, payload) = match json {
+                JsonValue::String(case_name) => (case_name, None),
+                JsonValue::Object(object) if object.len() == 1 => {
+                    let (case_name, payload) = object.iter().next().unwrap();
+                    (case_name, Some(payload))
+                }
+                other => return Err(type_mismatch("variant", other)),
+            };
+
+            let (case_idx, case) = variant
+                .cases
+                .iter()
+                .enumerate()
+                .find(|(_, case)| &case.name == case_name)
+                .ok_or_else(|| unknown_case_name(case_name))?;
+
+            let case_value = match (&case.typ, payload) {
+                (None, None) | (None, Some(JsonValue::Null)) => None,
+                (Some(typ), Some(payload)) => Some(Box::new(
+                    json_to_value(payload, typ).map_err(|e| e.at_field(case_name.clone()))?,
+                )),
+                (None, Some(_)) => {
+                    return Err(
+                        ConversionError::custom("unexpected payload").at_field(case_name.clone())
+                    )
+                }
+                (Some(_), None) => {
+                    return Err(
+                        ConversionError::custom("missing payload").at_field(case_name.clone())
+                    )
+                }
+            };
+
+            Ok(Value::Variant {
+                case_idx: case_idx as u32,
+                case_value,
+            })
+        }
+        AnalysedType::Enum(enum_type) => match json {
+            JsonValue::String(case_name) => enum_type
+                .cases
+                .iter()
+                .position(|case| case == case_name)
+                .map(|idx| Value::Enum(idx as u32))
+                .ok_or_else(|| unknown_case_name(case_name)),
+            other => Err(type_mismatch("enum", other)),
+        },
+        AnalysedType::Flags(flags) => match json {
+            JsonValue::Array(items) => {
+                let mut set = vec![false; flags.names.len()];
+
+                for (idx, item) in items.iter().enumerate() {
+                    let name = match item {
+                        JsonValue::String(name) => name,
+                        other => return Err(type_mismatch("string", other).at_index(idx)),
+                    };
+
+                    match flags.names.iter().position(|flag| flag == name) {
+                        Some(flag_idx) => set[flag_idx] = true,
+                        None => {
+                            return Err(ConversionError::custom(format!("unknown flag '{}'", name))
+                                .at_index(idx))
+                        }
+                    }
+                }
+
+                Ok(Value::Flags(set))
+            }
+            other => Err(type_mismatch("flags", other)),
+        },
+        AnalysedType::Option(option) => match json {
+            JsonValue::Null => Ok(Value::Option(None)),
+            other => Ok(Value::Option(Some(Box::new(json_to_value(
+                other,
+                &option.inner,
+            )?)))),
+        },
+        AnalysedType::Result(result) => {
+            let (is_ok, payload) = match json {
+                JsonValue::Object(object) if object.len() == 1 => match object.iter().next() {
+                    Some((key, payload)) if key == "ok" => (true, payload),
+                    Some((key, payload)) if key == "err" => (false, payload),
+                    _ => return Err(type_mismatch("result", json)),
+                },
+                other => return Err(type_mismatch("result", other)),
+            };
+
+            let (typ, key) = if is_ok {
+                (&result.ok, "ok")
+            } else {
+                (&result.err, "err")
+            };
+
+            let payload = match typ {
+                Some(typ) => Some(Box::new(
+                    json_to_value(payload, typ).map_err(|e| e.at_field(key))?,
+                )),
+                None => None,
+            };
+
+            Ok(Value::Result(if is_ok {
+                Ok(payload)
+            } else {
+                Err(payload)
+            }))
+        }
+        AnalysedType::Handle(_) => Err(ConversionError::custom(
+            "resource handles cannot be represented in JSON",
+        )),
+    }
+}
+
+pub fn value_to_json(value: &Value, typ: &AnalysedType) -> Result<JsonValue, ConversionError> {
+    match (typ, value) {
+        (AnalysedType::Bool(_), Value::Bool(b)) => Ok(JsonValue::Bool(*b)),
+        (AnalysedType::U8(_), Value::U8(n)) => Ok(JsonValue::from(*n)),
+        (AnalysedType::U16(_), Value::U16(n)) => Ok(JsonValue::from(*n)),
+        (AnalysedType::U32(_), Value::U32(n)) => Ok(JsonValue::from(*n)),
+        (AnalysedType::U64(_), Value::U64(n)) => Ok(JsonValue::from(*n)),
+        (AnalysedType::S8(_), Value::S8(n)) => Ok(JsonValue::from(*n)),
+        (AnalysedType::S16(_), Value::S16(n)) => Ok(JsonValue::from(*n)),
+        (AnalysedType::S32(_), Value::S32(n)) => Ok(JsonValue::from(*n)),
+        (AnalysedType::S64(_), Value::S64(n)) => Ok(JsonValue::from(*n)),
+        (AnalysedType::F32(_), Value::F32(f)) => finite(*f as f64),
+        (AnalysedType::F64(_), Value::F64(f)) => finite(*f),
+        (AnalysedType::Chr(_), Value::Char(c)) => Ok(JsonValue::String(c.to_string())),
+        (AnalysedType::Str(_), Value::String(s)) => Ok(JsonValue::String(s.clone())),
+        (AnalysedType::List(list), Value::List(items)) => items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| value_to_json(item, &list.inner).map_err(|e| e.at_index(idx)))
+            .collect::<Result<Vec<_>, _>>()
+            .map(JsonValue::Array),
+        (AnalysedType::Tuple(tuple), Value::Tuple(items)) if items.len() == tuple.items.len() => {
+            items
+                .iter()
+                .zip(&tuple.items)
+                .enumerate()
+                .map(|(idx, (item, typ))| value_to_json(item, typ).map_err(|e| e.at_index(idx)))
+                .collect::<Result<Vec<_>, _>>()
+                .map(JsonValue::Array)
+        }
+        (AnalysedType::Tuple(tuple), Value::Tuple(items)) => Err(ConversionError::length_mismatch(
+            tuple.items.len(),
+            items.len(),
+        )),
+        (AnalysedType::Record(record), Value::Record(values))
+            if values.len() == record.fields.len() =>
+        {
+            let mut object = Map::new();
+
+            for (field, value) in record.fields.iter().zip(values) {
+                let json =
+                    value_to_json(value, &field.typ).map_err(|e| e.at_field(field.name.clone()))?;
+                object.insert(field.name.clone(), json);
+            }
+
+            Ok(JsonValue::Object(object))
+        }
+        (AnalysedType::Record(record), Value::Record(values)) => Err(
+            ConversionError::length_mismatch(record.fields.len(), values.len()),
+        ),
+        (
+            AnalysedType::Variant(variant),
+            Value::Variant {
+                case_idx,
+                case_value,
+            },
+        ) => {
+            let case = variant
+                .cases
+                .get(*case_idx as usize)
+                .ok_or_else(|| ConversionError::unknown_case("variant", *case_idx))?;
+
+            match (&case.typ, case_value) {
+                (None, None) => Ok(JsonValue::String(case.name.clone())),
+                (Some(typ), Some(payload)) => {
+                    let json =
+                        value_to_json(payload, typ).map_err(|e| e.at_field(case.name.clone()))?;
+                    let mut object = Map::new();
+                    object.insert(case.name.clone(), json);
+                    Ok(JsonValue::Object(object))
+                }
+                (None, Some(_)) => {
+                    Err(ConversionError::custom("unexpected payload").at_field(case.name.clone()))
+                }
+                (Some(_), None) => {
+                    Err(ConversionError::custom("missing payload").at_field(case.name.clone()))
+                }
+            }
+        }
+        (AnalysedType::Enum(enum_type), Value::Enum(case_idx)) => enum_type
+            .cases
+            .get(*case_idx as usize)
+            .map(|case| JsonValue::String(case.clone()))
+            .ok_or_else(|| ConversionError::unknown_case("enum", *case_idx)),
+        (AnalysedType::Flags(flags), Value::Flags(set)) => Ok(JsonValue::Array(
+            flags
+                .names
+                .iter()
+                .zip(set)
+                .filter(|(_, is_set)| **is_set)
+                .map(|(name, _)| JsonValue::String(name.clone()))
+                .collect(),
+        )),
+        (AnalysedType::Option(_), Value::Option(None)) => Ok(JsonValue::Null),
+        (AnalysedType::Option(option), Value::Option(Some(inner))) => {
+            value_to_json(inner, &option.inner)
+        }
+        (AnalysedType::Result(result), Value::Result(value)) => {
+            let (key, typ, payload) = match value {
+                Ok(payload) => ("ok", &result.ok, payload),
+                Err(payload) => ("err", &result.err, payload),
+            };
+
+            let json = match (typ, payload) {
+                (Some(typ), Some(payload)) => {
+                    value_to_json(payload, typ).map_err(|e| e.at_field(key))?
+                }
+                _ => JsonValue::Null,
+            };
+
+            let mut object = Map::new();
+            object.insert(key.to_string(), json);
+            Ok(JsonValue::Object(object))
+        }
+        (AnalysedType::Handle(_), _) => Err(ConversionError::custom(
+            "resource handles cannot be represented in JSON",
+        )),
+        (typ, value) => Err(ConversionError::type_mismatch(wit_type_name(typ), value)),
+    }
+}
+
+fn integer<T: TryFrom<i64> + TryFrom<u64>>(
+    json: &JsonValue,
+    wit_name: &str,
+) -> Result<T, ConversionError> {
+    let number = match json {
+        JsonValue::Number(number) => number,
+        other => return Err(type_mismatch(wit_name, other)),
+    };
+
+    let converted = if let Some(n) = number.as_u64() {
+        T::try_from(n).ok()
+    } else if let Some(n) = number.as_i64() {
+        T::try_from(n).ok()
+    } else {
+        None
+    };
+
+    converted.ok_or_else(|| {
+        ConversionError::custom(format!("{} is not a valid {} value", number, wit_name))
+    })
+}
+
+fn float(json: &JsonValue, wit_name: &str) -> Result<f64, ConversionError> {
+    match json {
+        JsonValue::Number(number) => number.as_f64().ok_or_else(|| {
+            ConversionError::custom(format!("{} is not a valid {} value", number, wit_name))
+        }),
+        other => Err(type_mismatch(wit_name, other)),
+    }
+}
+
+fn finite(f: f64) -> Result<JsonValue, ConversionError> {
+    Number::from_f64(f)
+        .map(JsonValue::Number)
+        .ok_or_else(|| ConversionError::custom(format!("{} cannot be represented in JSON", f)))
+}
+
+fn unknown_case_name(case_name: &str) -> ConversionError {
+    ConversionError::custom(format!("unknown case '{}'", case_name))
+}
+
+fn type_mismatch(expected: &str, json: &JsonValue) -> ConversionError {
+    let actual = match json {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "a JSON boolean",
+        JsonValue::Number(_) => "a JSON number",
+        JsonValue::String(_) => "a JSON string",
+        JsonValue::Array(_) => "a JSON array",
+        JsonValue::Object(_) => "a JSON object",
+    };
+
+    ConversionError::new(ConversionErrorKind::TypeMismatch {
+        expected: expected.to_string(),
+        actual: actual.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::golem::agent::common::{NamedParameterType, Structured};
+    use golem_wasm_ast::analysis::analysed_type::{
+        bool, case, chr, f64, field, flags, list, option, r#enum, record, result, s32, str, tuple,
+        u64, u8, unit_case, variant,
+    };
+    use serde_json::json;
+
+    fn parameter(name: &str, typ: AnalysedType) -> NamedParameterType {
+        NamedParameterType {
+            name: name.to_string(),
+            parameter_type: ParameterType::Wit(WitType::from(typ)),
+        }
+    }
+
+    fn structured(parameters: Vec<NamedParameterType>) -> DataSchema {
+        DataSchema::Structured(Structured { parameters })
+    }
+
+    // Converts the JSON to a value and back, expecting the same JSON
+    fn round_trip(json: JsonValue, typ: AnalysedType) -> Value {
+        let value = json_to_value(&json, &typ).unwrap();
+        assert_eq!(value_to_json(&value, &typ).unwrap(), json);
+        value
+    }
+
+    #[test]
+    fn round_trips_primitives() {
+        assert_eq!(round_trip(json!(true), bool()), Value::Bool(true));
+        assert_eq!(round_trip(json!(-3), s32()), Value::S32(-3));
+        assert_eq!(round_trip(json!(u64::MAX), u64()), Value::U64(u64::MAX));
+        assert_eq!(round_trip(json!(0.5), f64()), Value::F64(0.5));
+        assert_eq!(round_trip(json!("x"), chr()), Value::Char('x'));
+        assert_eq!(
+            round_trip(json!("text"), str()),
+            Value::String("text".to_string())
+        );
+    }
+
+    #[test]
+    fn round_trips_compound_types() {
+        let line = record(vec![field("sku", str()), field("count", u8())]);
+
+        round_trip(json!([{"sku": "a", "count": 1}]), list(line));
+        round_trip(json!([1, "a"]), tuple(vec![u8(), str()]));
+        round_trip(json!(null), option(str()));
+        round_trip(json!("a"), option(str()));
+        round_trip(json!({"ok": 1}), result(u8(), str()));
+        round_trip(json!({"err": "failed"}), result(u8(), str()));
+        round_trip(json!("medium"), r#enum(&["small", "medium"]));
+        round_trip(json!(["read", "write"]), flags(&["read", "write", "admin"]));
+    }
+
+    #[test]
+    fn round_trips_variants() {
+        let shape = variant(vec![unit_case("empty"), case("circle", f64())]);
+
+        assert_eq!(
+            round_trip(json!("empty"), shape.clone()),
+            Value::Variant {
+                case_idx: 0,
+                case_value: None
+            }
+        );
+        round_trip(json!({"circle": 2.0}), shape);
+    }
+
+    #[test]
+    fn missing_record_fields_are_only_accepted_for_options() {
+        let typ = record(vec![field("sku", str()), field("note", option(str()))]);
+
+        assert_eq!(
+            json_to_value(&json!({"sku": "a"}), &typ).unwrap(),
+            Value::Record(vec![Value::String("a".to_string()), Value::Option(None)])
+        );
+        assert!(json_to_value(&json!({"note": "a"}), &typ).is_err());
+    }
+
+    #[test]
+    fn reports_the_path_of_invalid_values() {
+        let typ = list(record(vec![field("count", u8())]));
+        let error = json_to_value(&json!([{"count": 1}, {"count": 300}]), &typ).unwrap_err();
+
+        assert!(error.to_string().starts_with("[1].count: "), "{}", error);
+    }
+
+    #[test]
+    fn parses_arguments_given_as_array_or_object() {
+        let schema = structured(vec![
+            parameter("city", str()),
+            parameter("days", option(u8())),
+        ]);
+
+        let parse = |json: JsonValue| -> Vec<Value> {
+            parameters_from_json(&schema, &json)
+                .unwrap()
+                .into_iter()
+                .map(Value::from)
+                .collect()
+        };
+
+        assert_eq!(
+            parse(json!(["Paris", 3])),
+            vec![
+                Value::String("Paris".to_string()),
+                Value::Option(Some(Box::new(Value::U8(3))))
+            ]
+        );
+        assert_eq!(
+            parse(json!({"city": "Paris", "days": 3})),
+            parse(json!(["Paris", 3]))
+        );
+        assert_eq!(parse(json!({"city": "Paris"}))[1], Value::Option(None));
+        assert!(parameters_from_json(&schema, &json!({"town": "Paris"})).is_err());
+        assert!(parameters_from_json(&schema, &json!(["Paris"])).is_err());
+    }
+
+    #[test]
+    fn renders_method_outputs_and_errors() {
+        let method = AgentMethod {
+            name: "divide".to_string(),
+            description: String::new(),
+            prompt_hint: None,
+            input_schema: structured(vec![]),
+            output_schema: structured(vec![parameter("return-value", f64())]),
+            error_schema: Some(structured(vec![parameter("error", str())])),
+        };
+
+        assert_eq!(
+            method_output_to_json(&method, WitValue::from(Value::F64(2.5))).unwrap(),
+            json!(2.5)
+        );
+        assert_eq!(
+            method_error_to_json(&method, WitValue::from(Value::String("boom".to_string())))
+                .unwrap(),
+            json!("boom")
+        );
+    }
+}
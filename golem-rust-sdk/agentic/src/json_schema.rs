@@ -0,0 +1,421 @@
+// Renders agent methods as JSON Schema tool definitions, for LLM function calling.
+// The schemas describe the JSON representation accepted by `json::method_input_from_json`,
+// so the arguments of a tool call can be passed to it as they are.
+
+use crate::bindings::golem::agent::common::{AgentMethod, AgentType, DataSchema, ParameterType};
+use crate::conversion_error::ConversionError;
+use golem_wasm_ast::analysis::AnalysedType;
+use serde_json::{json, Map, Value as JsonValue};
+
+const TOOL_NAME_SEPARATOR: &str = "--";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToolDefinition {
+    // `{agent-type}--{method}`
+    pub name: String,
+    pub description: String,
+    // JSON Schema of an object with one property per method parameter
+    pub parameters: JsonValue,
+}
+
+impl ToolDefinition {
+    pub fn to_openai_json(&self) -> JsonValue {
+        json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.parameters,
+            }
+        })
+    }
+
+    pub fn to_anthropic_json(&self) -> JsonValue {
+        json!({
+            "name": self.name,
+            "description": self.description,
+            "input_schema": self.parameters,
+        })
+    }
+}
+
+// Fails if a method cannot be described as a tool, e.g. because its input is multimodal
+pub fn tool_definitions(agent_type: &AgentType) -> Result<Vec<ToolDefinition>, ConversionError> {
+    agent_type
+        .methods
+        .iter()
+        .map(|method| tool_definition(&agent_type.type_name, method))
+        .collect()
+}
+
+pub fn tool_definition(
+    agent_type_name: &str,
+    method: &AgentMethod,
+) -> Result<ToolDefinition, ConversionError> {
+    let description = match &method.prompt_hint {
+        Some(prompt_hint) if !prompt_hint.is_empty() => {
+            format!("{}\n\n{}", method.description, prompt_hint)
+        }
+        _ => method.description.clone(),
+    };
+
+    Ok(ToolDefinition {
+        name: tool_name(agent_type_name, &method.name)?,
+        description,
+        parameters: data_schema_to_json_schema(&method.input_schema)
+            .map_err(|e| e.at_field(&method.name))?,
+    })
+}
+
+// The agent type name must not contain the separator, so that `parse_tool_name` can split the
+// tool name at its first occurrence. Method names may contain it.
+pub fn tool_name(agent_type_name: &str, method_name: &str) -> Result<String, ConversionError> {
+    if agent_type_name.contains(TOOL_NAME_SEPARATOR) {
+        return Err(ConversionError::custom(format!(
+            "agent type name '{}' contains '{}', which separates it from the method name in tool names",
+            agent_type_name, TOOL_NAME_SEPARATOR
+        )));
+    }
+
+    Ok(format!(
+        "{}{}{}",
+        agent_type_name, TOOL_NAME_SEPARATOR, method_name
+    ))
+}
+
+// The inverse of `tool_name`, giving the agent type name and the method name
+pub fn parse_tool_name(tool_name: &str) -> Option<(&str, &str)> {
+    tool_name.split_once(TOOL_NAME_SEPARATOR)
+}
+
+pub fn data_schema_to_json_schema(schema: &DataSchema) -> Result<JsonValue, ConversionError> {
+    let parameters = match schema {
+        DataSchema::Structured(structured) => &structured.parameters,
+        DataSchema::Multimodal(_) => {
+            return Err(ConversionError::custom(
+                "multimodal schemas are not supported",
+            ))
+        }
+    };
+
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+
+    for parameter in parameters {
+        let name = parameter.name.clone();
+        let schema = match &parameter.parameter_type {
+            ParameterType::Wit(wit_type) => {
+                let typ = AnalysedType::from(wit_type.clone());
+                if !matches!(typ, AnalysedType::Option(_)) {
+                    required.push(JsonValue::String(name.clone()));
+                }
+                wit_type_to_json_schema(&typ)
+            }
+            ParameterType::Text(text_type) => {
+                required.push(JsonValue::String(name.clone()));
+                json!({
+                    "type": "string",
+                    "description": format!("Text in language '{}'", text_type.language_code),
+                })
+            }
+        };
+
+        properties.insert(name, schema);
+    }
+
+    Ok(json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    }))
+}
+
+pub fn wit_type_to_json_schema(typ: &AnalysedType) -> JsonValue {
+    match typ {
+        AnalysedType::Bool(_) => json!({ "type": "boolean" }),
+        AnalysedType::U8(_) => integer_schema(u8::MIN as i64, u8::MAX as u64),
+        AnalysedType::U16(_) => integer_schema(u16::MIN as i64, u16::MAX as u64),
+        AnalysedType::U32(_) => integer_schema(u32::MIN as i64, u32::MAX as u64),
+        AnalysedType::U64(_) => integer_schema(u64::MIN as i64, u64::MAX),
+        AnalysedType::S8(_) => integer_schema(i8::MIN as i64, i8::MAX as u64),
+        AnalysedType::S16(_) => integer_schema(i16::MIN as i64, i16::MAX as u64),
+        AnalysedType::S32(_) => integer_schema(i32::MIN as i64, i32::MAX as u64),
+        AnalysedType::S64(_) => integer_schema(i64::MIN, i64::MAX as u64),
+        AnalysedType::F32(_) | AnalysedType::F64(_) => json!({ "type": "number" }),
+        AnalysedType::Chr(_) => json!({ "type": "string", "minLength": 1, "maxLength": 1 }),
+        AnalysedType::Str(_) => json!({ "type": "string" }),
+        AnalysedType::List(list) => json!({
+            "type": "array",
+            "items": wit_type_to_json_schema(&list.inner),
+        }),
+        AnalysedType::Tuple(tuple) => json!({
+            "type": "array",
+            "prefixItems": tuple.items.iter().map(wit_type_to_json_schema).collect::<Vec<_>>(),
+            "minItems": tuple.items.len(),
+            "maxItems": tuple.items.len(),
+        }),
+        AnalysedType::Record(record) => {
+            let mut properties = Map::new();
+            let mut required = Vec::new();
+
+            for field in &record.fields {
+                if !matches!(field.typ, AnalysedType::Option(_)) {
+                    required.push(JsonValue::String(field.name.clone()));
+                }
+                properties.insert(field.name.clone(), wit_type_to_json_schema(&field.typ));
+            }
+
+            json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+                "additionalProperties": false,
+            })
+        }
+        AnalysedType::Variant(variant) => {
+            let unit_cases: Vec<_> = variant
+                .cases
+                .iter()
+                .filter(|case| case.typ.is_none())
+                .map(|case| JsonValue::String(case.name.clone()))
+                .collect();
+
+            let mut alternatives: Vec<_> = variant
+                .cases
+                .iter()
+                .filter_map(|case| {
+                    case.typ
+                        .as_ref()
+                        .map(|typ| single_property_object(&case.name, wit_type_to_json_schema(typ)))
+                })
+                .collect();
+
+            if !unit_cases.is_empty() {
+                alternatives.push(json!({ "type": "string", "enum": unit_cases }));
+            }
+
+            json!({ "oneOf": alternatives })
+        }
+        AnalysedType::Enum(enum_type) => json!({
+            "type": "string",
+            "enum": enum_type.cases,
+        }),
+        AnalysedType::Flags(flags) => json!({
+            "type": "array",
+            "items": { "type": "string", "enum": flags.names },
+            "uniqueItems": true,
+        }),
+        AnalysedType::Option(option) => json!({
+            "anyOf": [wit_type_to_json_schema(&option.inner), { "type": "null" }],
+        }),
+        AnalysedType::Result(result) => {
+            let payload = |typ: &Option<Box<AnalysedType>>| match typ {
+                Some(typ) => wit_type_to_json_schema(typ),
+                None => json!({ "type": "null" }),
+            };
+
+            json!({
+                "oneOf": [
+                    single_property_object("ok", payload(&result.ok)),
+                    single_property_object("err", payload(&result.err)),
+                ],
+            })
+        }
+        // Resource handles have no JSON representation, any value will be rejected
+        AnalysedType::Handle(_) => json!({ "not": {} }),
+    }
+}
+
+fn integer_schema(minimum: i64, maximum: u64) -> JsonValue {
+    json!({
+        "type": "integer",
+        "minimum": minimum,
+        "maximum": maximum,
+    })
+}
+
+fn single_property_object(name: &str, schema: JsonValue) -> JsonValue {
+    json!({
+        "type": "object",
+        "properties": { name: schema },
+        "required": [name],
+        "additionalProperties": false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::golem::agent::common::{Multimodal, NamedParameterType, Structured};
+    use golem_wasm_ast::analysis::analysed_type::{
+        case, field, option, record, result, result_err, s32, str, tuple, u8, unit_case, variant,
+    };
+
+    fn method(input_schema: DataSchema) -> AgentMethod {
+        AgentMethod {
+            name: "get-weather".to_string(),
+            description: "Gets the weather".to_string(),
+            prompt_hint: None,
+            input_schema,
+            output_schema: DataSchema::Structured(Structured { parameters: vec![] }),
+            error_schema: None,
+        }
+    }
+
+    #[test]
+    fn record_schema() {
+        let typ = record(vec![field("city", str()), field("days", option(u8()))]);
+
+        assert_eq!(
+            wit_type_to_json_schema(&typ),
+            json!({
+                "type": "object",
+                "properties": {
+                    "city": { "type": "string" },
+                    "days": {
+                        "anyOf": [
+                            { "type": "integer", "minimum": 0, "maximum": 255 },
+                            { "type": "null" },
+                        ],
+                    },
+                },
+                "required": ["city"],
+                "additionalProperties": false,
+            })
+        );
+    }
+
+    #[test]
+    fn variant_schema() {
+        let typ = variant(vec![case("celsius", s32()), unit_case("unknown")]);
+
+        assert_eq!(
+            wit_type_to_json_schema(&typ),
+            json!({
+                "oneOf": [
+                    {
+                        "type": "object",
+                        "properties": {
+                            "celsius": {
+                                "type": "integer",
+                                "minimum": i32::MIN,
+                                "maximum": i32::MAX,
+                            },
+                        },
+                        "required": ["celsius"],
+                        "additionalProperties": false,
+                    },
+                    { "type": "string", "enum": ["unknown"] },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn result_schema() {
+        let ok_and_err = result(str(), u8());
+        let err_only = result_err(str());
+
+        assert_eq!(
+            wit_type_to_json_schema(&ok_and_err),
+            json!({
+                "oneOf": [
+                    single_property_object("ok", json!({ "type": "string" })),
+                    single_property_object("err", integer_schema(0, 255)),
+                ],
+            })
+        );
+        assert_eq!(
+            wit_type_to_json_schema(&err_only),
+            json!({
+                "oneOf": [
+                    single_property_object("ok", json!({ "type": "null" })),
+                    single_property_object("err", json!({ "type": "string" })),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn option_schema() {
+        assert_eq!(
+            wit_type_to_json_schema(&option(str())),
+            json!({ "anyOf": [{ "type": "string" }, { "type": "null" }] })
+        );
+    }
+
+    #[test]
+    fn tuple_schema() {
+        assert_eq!(
+            wit_type_to_json_schema(&tuple(vec![str(), u8()])),
+            json!({
+                "type": "array",
+                "prefixItems": [{ "type": "string" }, integer_schema(0, 255)],
+                "minItems": 2,
+                "maxItems": 2,
+            })
+        );
+    }
+
+    #[test]
+    fn tool_names_round_trip() {
+        let name = tool_name("weather-agent", "get-weather").unwrap();
+
+        assert_eq!(name, "weather-agent--get-weather");
+        assert_eq!(
+            parse_tool_name(&name),
+            Some(("weather-agent", "get-weather"))
+        );
+    }
+
+    #[test]
+    fn tool_names_round_trip_with_separator_in_method_name() {
+        let name = tool_name("weather-agent", "get--weather").unwrap();
+
+        assert_eq!(
+            parse_tool_name(&name),
+            Some(("weather-agent", "get--weather"))
+        );
+    }
+
+    #[test]
+    fn separator_in_agent_type_name_is_rejected() {
+        assert!(tool_name("weather--agent", "get-weather").is_err());
+    }
+
+    #[test]
+    fn multimodal_input_is_rejected() {
+        let method = method(DataSchema::Multimodal(Multimodal { text: None }));
+
+        let error = tool_definition("weather-agent", &method).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "get-weather: multimodal schemas are not supported"
+        );
+    }
+
+    #[test]
+    fn tool_definition_lists_the_parameters() {
+        let method = method(DataSchema::Structured(Structured {
+            parameters: vec![NamedParameterType {
+                name: "location".to_string(),
+                parameter_type: ParameterType::Wit(str().into()),
+            }],
+        }));
+
+        let definition = tool_definition("weather-agent", &method).unwrap();
+
+        assert_eq!(definition.name, "weather-agent--get-weather");
+        assert_eq!(
+            definition.parameters,
+            json!({
+                "type": "object",
+                "properties": { "location": { "type": "string" } },
+                "required": ["location"],
+                "additionalProperties": false,
+            })
+        );
+    }
+}
@@ -1,18 +1,35 @@
 use crate::agent_registry::AgentId;
 use crate::bindings::exports::golem::agent::guest::{AgentRef, StatusUpdate};
 use crate::bindings::exports::golem::agent::guest::{AgentType, Guest, GuestAgent};
+use crate::bindings::golem::agent::common::Error;
 use crate::bindings::golem::api::host;
+use crate::validation::InputValidationError;
 use golem_wasm_rpc::WitValue;
 
 pub use type_mapping::*;
 pub use agent_construct::*;
+pub use conversion_error::*;
+pub use serde_bridge::Serde;
+pub use progress::ProgressReporter;
+pub use remote::RemoteAgentError;
+pub use stream::AgentStream;
 
 pub mod agent;
 pub mod agent_instance_registry;
 pub mod agent_registry;
 pub mod bindings;
+pub mod executor;
+pub mod json;
+pub mod json_schema;
+pub mod progress;
+pub mod remote;
+pub mod serde_bridge;
+pub mod stream;
+pub mod validation;
+mod panic_context;
 mod type_mapping;
 mod agent_construct;
+mod conversion_error;
 
 #[derive(Clone)]
 pub struct ResolvedAgent {
@@ -20,6 +37,40 @@ pub struct ResolvedAgent {
     pub agent_id: String,
 }
 
+impl ResolvedAgent {
+    // An agent whose constructor parameters were rejected. Resource constructors cannot fail, so
+    // invocations of the agent fail with the error instead.
+    fn rejected(agent_type: AgentType, error: InputValidationError) -> Self {
+        ResolvedAgent {
+            agent: ::std::sync::Arc::new(RejectedAgent {
+                agent_type,
+                error: error.into(),
+            }),
+            // Rejected agents are not registered, and have no id
+            agent_id: String::new(),
+        }
+    }
+}
+
+struct RejectedAgent {
+    agent_type: AgentType,
+    error: Error,
+}
+
+impl agent::Agent for RejectedAgent {
+    fn get_id(&self) -> String {
+        String::new()
+    }
+
+    fn invoke(&self, _method_name: String, _input: Vec<WitValue>) -> StatusUpdate {
+        StatusUpdate::Error(self.error.clone())
+    }
+
+    fn get_definition(&self) -> AgentType {
+        self.agent_type.clone()
+    }
+}
+
 struct Component;
 
 impl Guest for Component {
@@ -94,15 +145,17 @@ impl GuestAgent for ResolvedAgent {
 
         let agent_initiator = agent_registry::get_agent_initiator(agent_type.type_name.clone());
 
-        if let Some(agent) = agent_initiator {
-            let agent = agent.initiate(params);
-            agent
+        let agent = if let Some(agent) = agent_initiator {
+            validation::validate_inputs(&agent_type.agent_constructor.input_schema, &params)
+                .and_then(|()| agent.initiate(params))
         } else {
             panic!(
                 "No agent implementation found for agent definition: {}",
                 agent_type.type_name
             );
-        }
+        };
+
+        agent.unwrap_or_else(|error| ResolvedAgent::rejected(agent_type.clone(), error))
     }
 
     fn get_id(&self) -> String {
@@ -110,7 +163,41 @@ impl GuestAgent for ResolvedAgent {
     }
 
     fn invoke(&self, method_name: String, input: Vec<WitValue>) -> StatusUpdate {
-        self.agent.invoke(method_name, input)
+        let agent_type = self.agent.get_definition();
+
+        let method = match agent_type.methods.iter().find(|x| x.name == method_name) {
+            Some(method) => method,
+            None => {
+                return StatusUpdate::Error(Error::UnknownMethod(format!(
+                    "Method '{}' not found in agent '{}'",
+                    method_name, agent_type.type_name
+                )))
+            }
+        };
+
+        if let Err(error) = validation::validate_inputs(&method.input_schema, &input) {
+            return StatusUpdate::Error(error.into());
+        }
+
+        // The caller moved on from the stream returned by the previous invocation, if any
+        stream::close(&self.agent_id);
+
+        // A panic in the method still traps the invocation, see `panic_context`
+        panic_context::within_invocation(&self.agent_id, &method_name, || {
+            self.agent.invoke(method_name.clone(), input)
+        })
+    }
+
+    fn get_next(&self) -> StatusUpdate {
+        panic_context::within_invocation(&self.agent_id, "get-next", || {
+            stream::next(&self.agent_id)
+        })
+        .unwrap_or_else(|| {
+            StatusUpdate::Error(Error::UnknownMethod(format!(
+                "No stream is being pulled from agent '{}'",
+                self.agent_id
+            )))
+        })
     }
 
     fn get_definition(&self) -> AgentType {
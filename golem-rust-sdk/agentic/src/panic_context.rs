@@ -0,0 +1,67 @@
+// Identifies the agent and method in the message of panics raised by agent methods.
+//
+// A panic in an agent method is not contained: the invocation traps, and the host reports the
+// trap to the caller as a failed invocation. It is not turned into an error status, because
+// neither way of containing it works for components:
+//
+// - Catching the panic needs unwinding, but the wasm targets build the standard library with
+//   `panic = "abort"`. Unwinding would need a nightly toolchain rebuilding the standard library
+//   with `panic = "unwind"`, and the wasm exception handling proposal enabled in the runtime.
+// - Running the method in a sub-invocation, to observe its trap from the outside, would have to
+//   target the worker the agent lives in, as that is where its state is. A worker handles one
+//   invocation at a time, so it cannot wait for an invocation of itself.
+//
+// What remains is the panic hook, which is still called before aborting. It logs which agent and
+// method panicked, to tell a bug in an agent method apart from a failure of the infrastructure.
+// The message goes through `log`, so it reaches `wasi:logging` once the component installs
+// `wasi_logger::Logger`.
+
+use std::cell::RefCell;
+use std::sync::Once;
+
+thread_local! {
+    // The agent id and method name of the invocation being handled, `None` outside of one
+    static INVOCATION: RefCell<Option<(String, String)>> = const { RefCell::new(None) };
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+// Runs `f` as the invocation of `method_name` on the agent with `agent_id`
+pub(crate) fn within_invocation<R>(agent_id: &str, method_name: &str, f: impl FnOnce() -> R) -> R {
+    INSTALL_HOOK.call_once(install_hook);
+
+    let previous = INVOCATION.with(|invocation| {
+        invocation
+            .borrow_mut()
+            .replace((agent_id.to_string(), method_name.to_string()))
+    });
+    let result = f();
+    INVOCATION.with(|invocation| *invocation.borrow_mut() = previous);
+
+    result
+}
+
+// Whether an invocation is being handled
+pub(crate) fn is_within_invocation() -> bool {
+    INVOCATION.with(|invocation| invocation.borrow().is_some())
+}
+
+fn install_hook() {
+    let default_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        // `try_borrow` as the panic may come from within `within_invocation` itself
+        let invocation = INVOCATION.with(|invocation| {
+            invocation
+                .try_borrow()
+                .ok()
+                .and_then(|invocation| invocation.clone())
+        });
+
+        if let Some((agent_id, method_name)) = invocation {
+            log::error!("Agent {} panicked in method {}", agent_id, method_name);
+        }
+
+        default_hook(info);
+    }));
+}
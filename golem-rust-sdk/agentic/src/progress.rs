@@ -0,0 +1,55 @@
+// Progress reporting for long-running agent methods.
+//
+// Reports are passed to the host through the `golem:agent/progress` import as soon as they are
+// made, and the host delivers them to the caller while the method is still running. The result
+// of `invoke` only holds the final status of the invocation.
+//
+// Example usage:
+//
+// ```
+//  fn import(&self, files: Vec<String>) -> u32 {
+//      let progress = ProgressReporter::current();
+//      for (idx, file) in files.iter().enumerate() {
+//          progress.report_step(format!("Importing {}", file), idx as u32, files.len() as u32);
+//          ...
+//      }
+//  }
+// ```
+
+use crate::bindings::golem::agent::common::ProgressCounter;
+pub use crate::bindings::golem::agent::common::ProgressReport;
+use crate::bindings::golem::agent::progress;
+use crate::panic_context;
+
+#[derive(Clone, Copy, Debug)]
+pub struct ProgressReporter {
+    _private: (),
+}
+
+impl ProgressReporter {
+    // The reporter of the invocation being handled. Reports made outside of an
+    // invocation are discarded.
+    pub fn current() -> Self {
+        ProgressReporter { _private: () }
+    }
+
+    pub fn report(&self, description: impl Into<String>) {
+        send(ProgressReport {
+            description: description.into(),
+            counter: None,
+        });
+    }
+
+    pub fn report_step(&self, description: impl Into<String>, steps: u32, total: u32) {
+        send(ProgressReport {
+            description: description.into(),
+            counter: Some(ProgressCounter { steps, total }),
+        });
+    }
+}
+
+fn send(report: ProgressReport) {
+    if panic_context::is_within_invocation() {
+        progress::report(&report);
+    }
+}
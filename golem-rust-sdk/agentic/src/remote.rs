@@ -0,0 +1,767 @@
+// Helpers used by the generated `Remote*` clients to decode the results of remote invocations.
+
+use crate::agent::parse_agent_id;
+use crate::bindings::wasi::clocks::monotonic_clock;
+use crate::bindings::wasi::random::random;
+use crate::conversion_error::ConversionError;
+use crate::executor;
+use crate::stream::AgentStream;
+use crate::type_mapping::{FromValue, ResultPayload};
+use golem_wasm_rpc::{
+    CancellationToken, ComponentId, FutureInvokeResult, RpcError, Uri, Value, WitNode, WitValue,
+};
+use std::convert::Infallible;
+use std::time::Duration;
+
+pub use crate::bindings::golem::api::host::RetryPolicy;
+pub use golem_wasm_rpc::wasi::clocks::wall_clock::Datetime;
+
+// The error of a method called through a `Remote*` client. `E` is the error type of methods
+// returning `Result<T, E>`, and `Infallible` for all other methods.
+#[derive(Debug, thiserror::Error)]
+pub enum RemoteAgentError<E = Infallible> {
+    #[error("failed to call the remote agent: {0:?}")]
+    Rpc(RpcError),
+    #[error("the remote agent did not respond within {0:?}")]
+    Timeout(Duration),
+    #[error("the remote agent returned an error: {0:?}")]
+    Application(E),
+    #[error("the remote agent rejected the invocation: {0}")]
+    Invocation(String),
+    #[error("could not resolve the remote agent: {0}")]
+    Resolution(String),
+    #[error("failed to decode the result of the invocation: {0}")]
+    Decoding(ConversionError),
+}
+
+// Timeout and retries of the calls made through a `Remote*` client. Only protocol errors, which
+// are failures to communicate with the remote agent, are retried by default. Errors reported by
+// the agent itself, and RPC errors such as `denied` or `not-found` which do not go away when
+// retrying, are returned as is.
+#[derive(Clone, Debug, Default)]
+pub struct RemoteCallOptions {
+    pub timeout: Option<Duration>,
+    pub retry_policy: Option<RetryPolicy>,
+    pub retry_on_timeout: bool,
+}
+
+impl RemoteCallOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Gives up on an attempt which did not complete within `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    // Retries with the retry policy golem currently applies to this worker
+    pub fn with_worker_retry_policy(self) -> Self {
+        self.with_retry_policy(crate::bindings::golem::api::host::get_retry_policy())
+    }
+
+    // Also retries attempts which timed out. The invocation of a timed out attempt is not
+    // cancelled, and may still complete after the retry was started: this makes the call
+    // at-least-once, so it should only be used for idempotent methods.
+    pub fn with_retry_on_timeout(mut self) -> Self {
+        self.retry_on_timeout = true;
+        self
+    }
+
+    fn is_retryable(&self, error: &RemoteAgentError) -> bool {
+        match error {
+            RemoteAgentError::Rpc(RpcError::ProtocolError(_)) => true,
+            RemoteAgentError::Timeout(_) => self.retry_on_timeout,
+            _ => false,
+        }
+    }
+}
+
+// An invocation scheduled with one of the `schedule_*` methods of a `Remote*` client
+pub struct ScheduledInvocation {
+    method_name: &'static str,
+    scheduled_time: Datetime,
+    cancellation_token: CancellationToken,
+}
+
+impl ScheduledInvocation {
+    pub fn new(
+        method_name: &'static str,
+        scheduled_time: Datetime,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        ScheduledInvocation {
+            method_name,
+            scheduled_time,
+            cancellation_token,
+        }
+    }
+
+    pub fn method_name(&self) -> &str {
+        self.method_name
+    }
+
+    pub fn scheduled_time(&self) -> Datetime {
+        self.scheduled_time
+    }
+
+    // Cancels the invocation, which has no effect if it already ran
+    pub fn cancel(self) {
+        self.cancellation_token.cancel();
+    }
+}
+
+// Decodes the handle returned by the constructor of a remote agent, along with the name of the
+// worker the agent lives in, which is the last segment of the handle's URI
+pub fn decode_constructed_agent(
+    result: Result<WitValue, RpcError>,
+) -> Result<(Value, String), RemoteAgentError> {
+    match single_result(result)? {
+        Value::Handle { uri, resource_id } => {
+            let worker_name = match uri.rsplit_once('/') {
+                Some((_, worker_name)) if !worker_name.is_empty() => worker_name.to_string(),
+                _ => {
+                    return Err(RemoteAgentError::Decoding(ConversionError::custom(format!(
+                        "expected the URI of the agent handle to end with the worker name, but got '{}'",
+                        uri
+                    ))))
+                }
+            };
+
+            Ok((Value::Handle { uri, resource_id }, worker_name))
+        }
+        other => Err(RemoteAgentError::Decoding(ConversionError::type_mismatch(
+            "handle", &other,
+        ))),
+    }
+}
+
+// Decodes the `agent-ref` returned by `get-agent` into a handle to the agent, which lives in a
+// worker of the component `component_id`
+pub fn decode_agent_ref(
+    result: Result<WitValue, RpcError>,
+    component_id: &ComponentId,
+) -> Result<Value, RemoteAgentError> {
+    match single_result(result)? {
+        Value::Record(fields) => match fields.as_slice() {
+            [Value::String(agent_id), _, Value::U32(resource_id), ..] => {
+                let agent_info = parse_agent_id(agent_id).map_err(RemoteAgentError::Resolution)?;
+
+                Ok(Value::Handle {
+                    uri: format!("urn:worker:{}/{}", component_id, agent_info.worker_name),
+                    resource_id: *resource_id as u64,
+                })
+            }
+            _ => Err(RemoteAgentError::Decoding(ConversionError::custom(
+                "expected the agent ref to hold the agent id, type name and handle",
+            ))),
+        },
+        other => Err(RemoteAgentError::Decoding(ConversionError::type_mismatch(
+            "record", &other,
+        ))),
+    }
+}
+
+// The result of functions returning a single value is a tuple with that value
+fn single_result(result: Result<WitValue, RpcError>) -> Result<Value, RemoteAgentError> {
+    match Value::from(result.map_err(RemoteAgentError::Rpc)?) {
+        Value::Tuple(values) if values.len() == 1 => Ok(values.into_iter().next().unwrap()),
+        other => Err(RemoteAgentError::Decoding(ConversionError::type_mismatch(
+            "tuple", &other,
+        ))),
+    }
+}
+
+// Case indices of `status-update` in golem:agent/common
+const STATUS_UPDATE_ERROR: u32 = 0;
+const STATUS_UPDATE_EMIT: u32 = 2;
+const STATUS_UPDATE_COMPLETED: u32 = 3;
+
+// Case indices of `error` in golem:agent/common
+const ERROR_NETWORK_ERROR: u32 = 0;
+const ERROR_APPLICATION_ERROR: u32 = 1;
+const ERROR_UNKNOWN_METHOD: u32 = 2;
+const ERROR_ARITY_MISMATCH: u32 = 3;
+const ERROR_TYPE_MISMATCH: u32 = 4;
+
+// Waits for the result of an `async-invoke-and-await` without blocking other futures driven by
+// the same executor, so calls to several agents can proceed in parallel
+pub async fn await_invoke_result(future: FutureInvokeResult) -> Result<WitValue, RpcError> {
+    loop {
+        if let Some(result) = future.get() {
+            return result;
+        }
+
+        executor::wait_for(future.subscribe()).await;
+    }
+}
+
+// Calls `invoke` to start an `async-invoke-and-await`, and waits for its result according to the
+// timeout and retry policy of the options
+pub async fn invoke_with_options(
+    options: &RemoteCallOptions,
+    invoke: impl Fn() -> FutureInvokeResult,
+) -> Result<WitValue, RemoteAgentError> {
+    let mut attempt = 0;
+
+    loop {
+        let result = match options.timeout {
+            Some(timeout) => await_invoke_result_within(invoke(), timeout).await,
+            None => await_invoke_result(invoke())
+                .await
+                .map_err(RemoteAgentError::Rpc),
+        };
+
+        match (result, &options.retry_policy) {
+            (Err(error), Some(policy))
+                if options.is_retryable(&error) && attempt + 1 < policy.max_attempts =>
+            {
+                let delay = retry_delay(policy, attempt, random_fraction());
+                if delay > 0 {
+                    executor::wait_for(monotonic_clock::subscribe_duration(delay)).await;
+                }
+                attempt += 1;
+            }
+            (result, _) => return result,
+        }
+    }
+}
+
+// Like `await_invoke_result`, but stops waiting once `timeout` elapsed. The invocation itself is
+// not cancelled, the remote agent may still complete it.
+async fn await_invoke_result_within(
+    future: FutureInvokeResult,
+    timeout: Duration,
+) -> Result<WitValue, RemoteAgentError> {
+    let deadline = monotonic_clock::now().saturating_add(duration_nanos(timeout));
+
+    loop {
+        if let Some(result) = future.get() {
+            return result.map_err(RemoteAgentError::Rpc);
+        }
+
+        if monotonic_clock::now() >= deadline {
+            return Err(RemoteAgentError::Timeout(timeout));
+        }
+
+        executor::wait_for_any(vec![
+            future.subscribe(),
+            monotonic_clock::subscribe_instant(deadline),
+        ])
+        .await;
+    }
+}
+
+// The delay in nanoseconds before the retry following the given (zero based) attempt, growing
+// from `min-delay` by `multiplier` up to `max-delay`, with up to `max-jitter-factor` added in
+// proportion to `jitter_fraction`, a number in [0, 1)
+fn retry_delay(policy: &RetryPolicy, attempt: u32, jitter_fraction: f64) -> u64 {
+    let delay = (policy.min_delay as f64 * policy.multiplier.powi(attempt as i32))
+        .min(policy.max_delay as f64);
+
+    let jitter = match policy.max_jitter_factor {
+        Some(max_jitter_factor) => delay * max_jitter_factor * jitter_fraction,
+        None => 0.0,
+    };
+
+    (delay + jitter) as u64
+}
+
+// A random number in [0, 1)
+fn random_fraction() -> f64 {
+    (random::get_random_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn duration_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+// Decodes the value emitted by a remote `invoke`, whose result is a single `status-update`
+pub fn decode_emitted<T: FromValue>(
+    result: Result<WitValue, RemoteAgentError>,
+) -> Result<T, RemoteAgentError> {
+    match status_update(result)? {
+        Value::Variant {
+            case_idx: STATUS_UPDATE_EMIT,
+            case_value: Some(payload),
+        } => decode_payload(*payload),
+        other => Err(status_update_error(other)),
+    }
+}
+
+// Checks that a remote `invoke` of a method without a return value completed
+pub fn decode_completed(
+    result: Result<WitValue, RemoteAgentError>,
+) -> Result<(), RemoteAgentError> {
+    match status_update(result)? {
+        Value::Variant {
+            case_idx: STATUS_UPDATE_COMPLETED,
+            case_value: None,
+        } => Ok(()),
+        other => Err(status_update_error(other)),
+    }
+}
+
+// Decodes the outcome of a remote `invoke` of a method returning `Result<T, E>`, where `E` is
+// reported by the agent as an application error. The agent completes without emitting a value
+// if `T` is `()`.
+pub fn decode_result<T: ResultPayload, E: FromValue>(
+    result: Result<WitValue, RemoteAgentError>,
+) -> Result<T, RemoteAgentError<E>> {
+    match status_update(result)? {
+        Value::Variant {
+            case_idx: STATUS_UPDATE_EMIT,
+            case_value: Some(payload),
+        } => {
+            let value = decode_wit_value(*payload).map_err(RemoteAgentError::Decoding)?;
+            T::from_payload(Some(Value::from(value))).map_err(RemoteAgentError::Decoding)
+        }
+        Value::Variant {
+            case_idx: STATUS_UPDATE_COMPLETED,
+            case_value: None,
+        } => T::from_payload(None).map_err(RemoteAgentError::Decoding),
+        Value::Variant {
+            case_idx: STATUS_UPDATE_ERROR,
+            case_value: Some(error),
+        } => match *error {
+            Value::Variant {
+                case_idx: ERROR_APPLICATION_ERROR,
+                case_value: Some(payload),
+            } => Err(RemoteAgentError::Application(decode_payload(*payload)?)),
+            other => Err(error_status_error(other)),
+        },
+        other => Err(status_update_error(other)),
+    }
+}
+
+// Decodes the first chunk emitted by a remote `invoke` of a method returning an `AgentStream`.
+// The following chunks are pulled from the remote agent with `get_next`, which calls its
+// `get-next`, one at a time as the returned stream is consumed. The stream ends after the first
+// error, which is returned as its last item.
+pub fn decode_stream<T: FromValue + 'static>(
+    result: Result<WitValue, RemoteAgentError>,
+    mut get_next: impl FnMut() -> Result<WitValue, RpcError> + 'static,
+) -> Result<AgentStream<Result<T, RemoteAgentError>>, RemoteAgentError> {
+    let mut first_chunk = decode_chunk(result, 0)?;
+    let mut finished = first_chunk.is_none();
+    let mut idx = 0;
+
+    Ok(AgentStream::new(std::iter::from_fn(move || {
+        if let Some(chunk) = first_chunk.take() {
+            return Some(Ok(chunk));
+        }
+
+        if finished {
+            return None;
+        }
+
+        idx += 1;
+
+        match decode_chunk(get_next().map_err(RemoteAgentError::Rpc), idx) {
+            Ok(Some(chunk)) => Some(Ok(chunk)),
+            Ok(None) => {
+                finished = true;
+                None
+            }
+            Err(error) => {
+                finished = true;
+                Some(Err(error))
+            }
+        }
+    })))
+}
+
+// Decodes the chunk at `idx` of a stream, `None` if the stream completed instead
+fn decode_chunk<T: FromValue>(
+    result: Result<WitValue, RemoteAgentError>,
+    idx: usize,
+) -> Result<Option<T>, RemoteAgentError> {
+    match status_update(result)? {
+        Value::Variant {
+            case_idx: STATUS_UPDATE_EMIT,
+            case_value: Some(payload),
+        } => decode_payload(*payload).map(Some).map_err(|e| match e {
+            RemoteAgentError::Decoding(e) => RemoteAgentError::Decoding(e.at_index(idx)),
+            other => other,
+        }),
+        Value::Variant {
+            case_idx: STATUS_UPDATE_COMPLETED,
+            case_value: None,
+        } => Ok(None),
+        other => Err(status_update_error(other)),
+    }
+}
+
+fn decode_payload<T: FromValue, E>(payload: Value) -> Result<T, RemoteAgentError<E>> {
+    let value = decode_wit_value(payload).map_err(RemoteAgentError::Decoding)?;
+    T::from_value(Value::from(value)).map_err(RemoteAgentError::Decoding)
+}
+
+// The result of `invoke` is a tuple with the final status update of the invocation
+fn status_update<E>(
+    result: Result<WitValue, RemoteAgentError>,
+) -> Result<Value, RemoteAgentError<E>> {
+    match Value::from(result.map_err(call_error)?) {
+        Value::Tuple(values) if values.len() == 1 => Ok(values.into_iter().next().unwrap()),
+        other => Err(RemoteAgentError::Decoding(ConversionError::type_mismatch(
+            "tuple", &other,
+        ))),
+    }
+}
+
+// Converts an error of the call itself, which is never an application error
+fn call_error<E>(error: RemoteAgentError) -> RemoteAgentError<E> {
+    match error {
+        RemoteAgentError::Rpc(error) => RemoteAgentError::Rpc(error),
+        RemoteAgentError::Timeout(timeout) => RemoteAgentError::Timeout(timeout),
+        RemoteAgentError::Application(never) => match never {},
+        RemoteAgentError::Invocation(message) => RemoteAgentError::Invocation(message),
+        RemoteAgentError::Resolution(message) => RemoteAgentError::Resolution(message),
+        RemoteAgentError::Decoding(error) => RemoteAgentError::Decoding(error),
+    }
+}
+
+// The error for a final status update which does not match the return type of the method
+fn status_update_error<E>(status_update: Value) -> RemoteAgentError<E> {
+    match status_update {
+        Value::Variant {
+            case_idx: STATUS_UPDATE_ERROR,
+            case_value: Some(error),
+        } => error_status_error(*error),
+        other => RemoteAgentError::Decoding(ConversionError::custom(format!(
+            "unexpected status update {:?}",
+            other
+        ))),
+    }
+}
+
+fn error_status_error<E>(error: Value) -> RemoteAgentError<E> {
+    let (case_idx, details) = match error {
+        Value::Variant {
+            case_idx,
+            case_value,
+        } => (case_idx, case_value.map(|details| *details)),
+        other => {
+            return RemoteAgentError::Decoding(ConversionError::type_mismatch(
+                "error variant",
+                &other,
+            ))
+        }
+    };
+
+    match (case_idx, details) {
+        (ERROR_NETWORK_ERROR, None) => RemoteAgentError::Invocation("network error".to_string()),
+        (ERROR_UNKNOWN_METHOD, Some(Value::String(message))) => {
+            RemoteAgentError::Invocation(message)
+        }
+        (ERROR_ARITY_MISMATCH, Some(Value::Record(fields))) => match fields.as_slice() {
+            [Value::U32(expected), Value::U32(actual)] => RemoteAgentError::Invocation(format!(
+                "expected {} arguments, but got {}",
+                expected, actual
+            )),
+            _ => invalid_error_details("arity-mismatch", fields),
+        },
+        (ERROR_TYPE_MISMATCH, Some(Value::Record(fields))) => match fields.as_slice() {
+            [Value::String(parameter), Value::String(details)] => RemoteAgentError::Invocation(
+                format!("invalid value for parameter '{}': {}", parameter, details),
+            ),
+            _ => invalid_error_details("type-mismatch", fields),
+        },
+        (case_idx, _) => {
+            RemoteAgentError::Decoding(ConversionError::unknown_case("error", case_idx))
+        }
+    }
+}
+
+fn invalid_error_details<E>(case_name: &str, fields: Vec<Value>) -> RemoteAgentError<E> {
+    RemoteAgentError::Decoding(ConversionError::custom(format!(
+        "invalid details of {}: {:?}",
+        case_name, fields
+    )))
+}
+
+// A `wit-value` passed as a value of another `wit-value` (like the payload of `emit`) arrives as
+// the generic representation of the `wit-value` record, so it has to be decoded node by node
+pub fn decode_wit_value(value: Value) -> Result<WitValue, ConversionError> {
+    let nodes = match value {
+        Value::Record(fields) if fields.len() == 1 => fields.into_iter().next().unwrap(),
+        other => return Err(ConversionError::type_mismatch("wit-value record", &other)),
+    };
+
+    let nodes = match nodes {
+        Value::List(nodes) => nodes,
+        other => return Err(ConversionError::type_mismatch("list", &other).at_field("nodes")),
+    };
+
+    let nodes = nodes
+        .into_iter()
+        .enumerate()
+        .map(|(idx, node)| decode_wit_node(node).map_err(|e| e.at_index(idx).at_field("nodes")))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(WitValue { nodes })
+}
+
+fn decode_wit_node(value: Value) -> Result<WitNode, ConversionError> {
+    let (case_idx, payload) = match value {
+        Value::Variant {
+            case_idx,
+            case_value: Some(payload),
+        } => (case_idx, *payload),
+        other => return Err(ConversionError::type_mismatch("wit-node variant", &other)),
+    };
+
+    let node = match case_idx {
+        0 => WitNode::RecordValue(FromValue::from_value(payload)?),
+        1 => WitNode::VariantValue(FromValue::from_value(payload)?),
+        2 => WitNode::EnumValue(FromValue::from_value(payload)?),
+        3 => WitNode::FlagsValue(FromValue::from_value(payload)?),
+        4 => WitNode::TupleValue(FromValue::from_value(payload)?),
+        5 => WitNode::ListValue(FromValue::from_value(payload)?),
+        6 => WitNode::OptionValue(FromValue::from_value(payload)?),
+        7 => WitNode::ResultValue(FromValue::from_value(payload)?),
+        8 => WitNode::PrimU8(FromValue::from_value(payload)?),
+        9 => WitNode::PrimU16(FromValue::from_value(payload)?),
+        10 => WitNode::PrimU32(FromValue::from_value(payload)?),
+        11 => WitNode::PrimU64(FromValue::from_value(payload)?),
+        12 => WitNode::PrimS8(FromValue::from_value(payload)?),
+        13 => WitNode::PrimS16(FromValue::from_value(payload)?),
+        14 => WitNode::PrimS32(FromValue::from_value(payload)?),
+        15 => WitNode::PrimS64(FromValue::from_value(payload)?),
+        16 => WitNode::PrimFloat32(FromValue::from_value(payload)?),
+        17 => WitNode::PrimFloat64(FromValue::from_value(payload)?),
+        18 => WitNode::PrimChar(FromValue::from_value(payload)?),
+        19 => WitNode::PrimBool(FromValue::from_value(payload)?),
+        20 => WitNode::PrimString(FromValue::from_value(payload)?),
+        21 => {
+            let (uri, resource_id): (Vec<String>, u64) = match payload {
+                Value::Tuple(items) if items.len() == 2 => {
+                    let mut items = items.into_iter();
+                    let uri = match items.next().unwrap() {
+                        Value::Record(fields) => FromValue::from_value(Value::List(fields))?,
+                        other => return Err(ConversionError::type_mismatch("uri record", &other)),
+                    };
+                    (uri, FromValue::from_value(items.next().unwrap())?)
+                }
+                other => return Err(ConversionError::type_mismatch("tuple", &other)),
+            };
+
+            match uri.into_iter().next() {
+                Some(value) => WitNode::Handle((Uri { value }, resource_id)),
+                None => return Err(ConversionError::missing_field("value")),
+            }
+        }
+        other => return Err(ConversionError::unknown_case("wit-node", other)),
+    };
+
+    Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    const MILLIS: u64 = 1_000_000;
+
+    fn policy(max_jitter_factor: Option<f64>) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 5,
+            min_delay: 100 * MILLIS,
+            max_delay: 1_000 * MILLIS,
+            multiplier: 2.0,
+            max_jitter_factor,
+        }
+    }
+
+    #[test]
+    fn retry_delay_grows_by_the_multiplier() {
+        let policy = policy(None);
+
+        assert_eq!(retry_delay(&policy, 0, 0.5), 100 * MILLIS);
+        assert_eq!(retry_delay(&policy, 1, 0.5), 200 * MILLIS);
+        assert_eq!(retry_delay(&policy, 2, 0.5), 400 * MILLIS);
+        assert_eq!(retry_delay(&policy, 3, 0.5), 800 * MILLIS);
+    }
+
+    #[test]
+    fn retry_delay_is_capped_by_the_max_delay() {
+        let policy = policy(None);
+
+        assert_eq!(retry_delay(&policy, 4, 0.0), 1_000 * MILLIS);
+        assert_eq!(retry_delay(&policy, 30, 0.0), 1_000 * MILLIS);
+    }
+
+    #[test]
+    fn retry_delay_adds_jitter_up_to_the_max_jitter_factor() {
+        let policy = policy(Some(0.5));
+
+        assert_eq!(retry_delay(&policy, 1, 0.0), 200 * MILLIS);
+        assert_eq!(retry_delay(&policy, 1, 0.5), 250 * MILLIS);
+        assert!(retry_delay(&policy, 1, 0.999) < 300 * MILLIS);
+        // The jitter is added on top of the capped delay
+        assert_eq!(retry_delay(&policy, 10, 0.5), 1_250 * MILLIS);
+    }
+
+    #[test]
+    fn only_protocol_errors_are_retried_by_default() {
+        let options = RemoteCallOptions::new();
+
+        assert!(
+            options.is_retryable(&RemoteAgentError::Rpc(RpcError::ProtocolError(
+                "connection reset".to_string()
+            )))
+        );
+        assert!(
+            !options.is_retryable(&RemoteAgentError::Rpc(RpcError::Denied(
+                "denied".to_string()
+            )))
+        );
+        assert!(
+            !options.is_retryable(&RemoteAgentError::Rpc(RpcError::NotFound(
+                "not found".to_string()
+            )))
+        );
+        assert!(
+            !options.is_retryable(&RemoteAgentError::Rpc(RpcError::RemoteInternalError(
+                "failed".to_string()
+            )))
+        );
+        assert!(!options.is_retryable(&RemoteAgentError::Invocation("failed".to_string())));
+        assert!(!options.is_retryable(&RemoteAgentError::Timeout(Duration::from_secs(1))));
+    }
+
+    #[test]
+    fn timeouts_are_retried_when_opted_in() {
+        let options = RemoteCallOptions::new().with_retry_on_timeout();
+
+        assert!(options.is_retryable(&RemoteAgentError::Timeout(Duration::from_secs(1))));
+        assert!(
+            options.is_retryable(&RemoteAgentError::Rpc(RpcError::ProtocolError(
+                "connection reset".to_string()
+            )))
+        );
+    }
+
+    fn emit(chunk: u32) -> Value {
+        // The emitted `wit-value` holds a single `prim-u32` node
+        let node = Value::Variant {
+            case_idx: 10,
+            case_value: Some(Box::new(Value::U32(chunk))),
+        };
+
+        Value::Variant {
+            case_idx: STATUS_UPDATE_EMIT,
+            case_value: Some(Box::new(Value::Record(vec![Value::List(vec![node])]))),
+        }
+    }
+
+    fn completed() -> Value {
+        Value::Variant {
+            case_idx: STATUS_UPDATE_COMPLETED,
+            case_value: None,
+        }
+    }
+
+    fn invoke_result(status_update: Value) -> WitValue {
+        WitValue::from(Value::Tuple(vec![status_update]))
+    }
+
+    // Answers the pulls with `results` in order, counting them in `pulls`
+    fn get_next(
+        results: Vec<Result<WitValue, RpcError>>,
+        pulls: Rc<Cell<usize>>,
+    ) -> impl FnMut() -> Result<WitValue, RpcError> + 'static {
+        let mut results = VecDeque::from(results);
+
+        move || {
+            pulls.set(pulls.get() + 1);
+            results
+                .pop_front()
+                .expect("pulled past the end of the stream")
+        }
+    }
+
+    #[test]
+    fn chunks_are_pulled_as_the_stream_is_consumed() {
+        let pulls = Rc::new(Cell::new(0));
+        let get_next = get_next(
+            vec![Ok(invoke_result(emit(2))), Ok(invoke_result(completed()))],
+            pulls.clone(),
+        );
+
+        let mut stream = decode_stream::<u32>(Ok(invoke_result(emit(1))), get_next).unwrap();
+        assert_eq!(pulls.get(), 0);
+
+        assert_eq!(stream.next().unwrap().unwrap(), 1);
+        assert_eq!(pulls.get(), 0);
+
+        assert_eq!(stream.next().unwrap().unwrap(), 2);
+        assert_eq!(pulls.get(), 1);
+
+        assert!(stream.next().is_none());
+        assert!(stream.next().is_none());
+        assert_eq!(pulls.get(), 2);
+    }
+
+    #[test]
+    fn streams_completed_by_invoke_are_empty() {
+        let pulls = Rc::new(Cell::new(0));
+        let get_next = get_next(vec![], pulls.clone());
+
+        let stream = decode_stream::<u32>(Ok(invoke_result(completed())), get_next).unwrap();
+
+        assert_eq!(stream.count(), 0);
+        assert_eq!(pulls.get(), 0);
+    }
+
+    #[test]
+    fn failed_pulls_end_the_stream() {
+        let pulls = Rc::new(Cell::new(0));
+        let get_next = get_next(
+            vec![Err(RpcError::Denied("denied".to_string()))],
+            pulls.clone(),
+        );
+
+        let mut stream = decode_stream::<u32>(Ok(invoke_result(emit(1))), get_next).unwrap();
+
+        assert_eq!(stream.next().unwrap().unwrap(), 1);
+        assert!(matches!(
+            stream.next(),
+            Some(Err(RemoteAgentError::Rpc(RpcError::Denied(_))))
+        ));
+        assert!(stream.next().is_none());
+        assert_eq!(pulls.get(), 1);
+    }
+
+    #[test]
+    fn chunks_failing_to_decode_are_reported_with_their_index() {
+        let pulls = Rc::new(Cell::new(0));
+        let get_next = get_next(
+            vec![Ok(invoke_result(Value::Variant {
+                case_idx: STATUS_UPDATE_EMIT,
+                case_value: Some(Box::new(Value::Bool(true))),
+            }))],
+            pulls.clone(),
+        );
+
+        let mut stream = decode_stream::<u32>(Ok(invoke_result(emit(1))), get_next).unwrap();
+
+        assert_eq!(stream.next().unwrap().unwrap(), 1);
+        match stream.next() {
+            Some(Err(RemoteAgentError::Decoding(error))) => {
+                assert!(error.to_string().starts_with("[1]"), "{}", error)
+            }
+            _ => panic!("expected the second chunk to fail to decode"),
+        }
+        assert!(stream.next().is_none());
+    }
+}
@@ -0,0 +1,1743 @@
+// Maps the serde data model onto `golem_wasm_rpc::Value`, so that types which already implement
+// `Serialize` and `Deserialize` can be used as agent parameters and return values, either by
+// wrapping them in `Serde<T>` or with `#[derive(AgentArg)]` and `#[agent_arg(serde)]`.
+//
+// The mapping is:
+//   - primitives and strings map to the WIT primitives (`i128` and `u128` are not supported)
+//   - `Option<T>` maps to `option<t>`, sequences to `list<t>` and maps to `list<tuple<k, v>>`
+//   - tuples and tuple structs map to `tuple<..>`
+//   - unit and unit structs have no counterpart in WIT, they are only supported as the payload of
+//     a newtype variant, which maps to a case without payload (e.g. `Ok(())` of a `Result<(), E>`)
+//   - structs map to records, and newtype structs are transparent
+//   - enums always map to variants, even if all their variants are units
+//
+// The WIT type is inferred by running the type's `Deserialize` implementation against a tracing
+// deserializer, which records what it is asked for and feeds back placeholder values. Every enum
+// variant is visited in a separate pass. As a consequence, the following are not supported:
+//   - internally tagged, adjacently tagged and untagged enums, and `#[serde(flatten)]`,
+//     because they rely on `deserialize_any`
+//   - `#[serde(skip_serializing_if = "...")]`, as records have a fixed number of fields
+//   - skipped enum variants, as the variants are identified by their index
+//   - recursive types, which cannot be represented in WIT
+//   - types whose `Deserialize` implementation rejects the placeholder values
+//     (for example types parsed from strings)
+
+use crate::conversion_error::{value_kind, ConversionError};
+use crate::type_mapping::{FromValue, ToValue, ToWitType};
+use golem_wasm_ast::analysis::{analysed_type, AnalysedType, NameTypePair};
+use golem_wasm_rpc::{Value, WitType};
+use serde::de::{
+    DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+use serde::ser::{
+    SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant,
+};
+use serde::{Deserializer, Serializer};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::ops::{Deref, DerefMut};
+
+pub use serde::de::DeserializeOwned;
+pub use serde::Serialize;
+
+// Wraps any serde-compatible type to make it usable as an agent parameter or return value
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Serde<T>(pub T);
+
+impl<T> Serde<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Serde<T> {
+    fn from(value: T) -> Self {
+        Serde(value)
+    }
+}
+
+impl<T> Deref for Serde<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Serde<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Serialize> ToValue for Serde<T> {
+    fn to_value(&self) -> Value {
+        to_value(&self.0).unwrap_or_else(|e| panic!("Failed to convert to a WIT value: {}", e))
+    }
+}
+
+impl<T: DeserializeOwned> FromValue for Serde<T> {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        from_value(value).map(Serde)
+    }
+}
+
+impl<T: DeserializeOwned> ToWitType for Serde<T> {
+    fn get_wit_type() -> WitType {
+        WitType::from(
+            wit_type::<T>().unwrap_or_else(|e| panic!("Failed to infer the WIT type: {}", e)),
+        )
+    }
+}
+
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, ConversionError> {
+    value.serialize(ValueSerializer)
+}
+
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, ConversionError> {
+    T::deserialize(ValueDeserializer { value })
+}
+
+// Every pass visits the enum variants which have not been seen yet,
+// until the cases of all the enums reachable from `T` are known
+pub fn wit_type<T: DeserializeOwned>() -> Result<AnalysedType, ConversionError> {
+    let mut state = TraceState::default();
+
+    for _ in 0..MAX_TRACE_PASSES {
+        let mut result = None;
+
+        T::deserialize(Tracer {
+            state: &mut state,
+            path: String::new(),
+            depth: 0,
+            out: &mut result,
+        })?;
+
+        if !state.has_incomplete_enums_under("") {
+            return result.ok_or_else(no_wit_type);
+        }
+    }
+
+    Err(ConversionError::custom(
+        "could not discover all the cases of the enums",
+    ))
+}
+
+// Units leave the type unset, as they have no counterpart in WIT
+fn no_wit_type() -> ConversionError {
+    ConversionError::custom(
+        "could not infer the WIT type, units are only supported as the payload of a newtype variant",
+    )
+}
+
+impl serde::ser::Error for ConversionError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ConversionError::custom(msg.to_string())
+    }
+}
+
+impl serde::de::Error for ConversionError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ConversionError::custom(msg.to_string())
+    }
+}
+
+// Serialization
+
+struct ValueSerializer;
+
+impl Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = ConversionError;
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeCase;
+    type SerializeMap = SerializeEntries;
+    type SerializeStruct = SerializeRecord;
+    type SerializeStructVariant = SerializeCase;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, ConversionError> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, ConversionError> {
+        Ok(Value::S8(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, ConversionError> {
+        Ok(Value::S16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, ConversionError> {
+        Ok(Value::S32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, ConversionError> {
+        Ok(Value::S64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, ConversionError> {
+        Ok(Value::U8(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, ConversionError> {
+        Ok(Value::U16(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, ConversionError> {
+        Ok(Value::U32(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, ConversionError> {
+        Ok(Value::U64(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, ConversionError> {
+        Ok(Value::F32(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, ConversionError> {
+        Ok(Value::F64(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, ConversionError> {
+        Ok(Value::Char(v))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, ConversionError> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, ConversionError> {
+        Ok(Value::List(v.iter().map(|b| Value::U8(*b)).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Value, ConversionError> {
+        Ok(Value::Option(None))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, ConversionError> {
+        Ok(Value::Option(Some(Box::new(value.serialize(self)?))))
+    }
+
+    // Only valid as the payload of a newtype variant, which drops it
+    fn serialize_unit(self) -> Result<Value, ConversionError> {
+        Ok(Value::Tuple(vec![]))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, ConversionError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Value, ConversionError> {
+        Ok(Value::Variant {
+            case_idx: variant_index,
+            case_value: None,
+        })
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, ConversionError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<Value, ConversionError> {
+        // A unit payload makes a case without payload
+        let case_value = match value.serialize(self)? {
+            Value::Tuple(items) if items.is_empty() => None,
+            payload => Some(Box::new(payload)),
+        };
+
+        Ok(Value::Variant {
+            case_idx: variant_index,
+            case_value,
+        })
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList, ConversionError> {
+        Ok(SerializeList {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            is_tuple: false,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, ConversionError> {
+        Ok(SerializeList {
+            items: Vec::with_capacity(len),
+            is_tuple: true,
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, ConversionError> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeCase, ConversionError> {
+        Ok(SerializeCase {
+            case_idx: variant_index,
+            values: Vec::with_capacity(len),
+            is_record: false,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeEntries, ConversionError> {
+        Ok(SerializeEntries {
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeRecord, ConversionError> {
+        Ok(SerializeRecord {
+            values: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeCase, ConversionError> {
+        Ok(SerializeCase {
+            case_idx: variant_index,
+            values: Vec::with_capacity(len),
+            is_record: true,
+        })
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct SerializeList {
+    items: Vec<Value>,
+    is_tuple: bool,
+}
+
+impl SerializeList {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        let idx = self.items.len();
+        let value = value
+            .serialize(ValueSerializer)
+            .map_err(|e| e.at_index(idx))?;
+        self.items.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        if self.is_tuple {
+            Value::Tuple(self.items)
+        } else {
+            Value::List(self.items)
+        }
+    }
+}
+
+impl SerializeSeq for SerializeList {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), ConversionError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTuple for SerializeList {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), ConversionError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTupleStruct for SerializeList {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(self.finish())
+    }
+}
+
+struct SerializeEntries {
+    entries: Vec<Value>,
+    key: Option<Value>,
+}
+
+impl SerializeMap for SerializeEntries {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), ConversionError> {
+        let idx = self.entries.len();
+        self.key = Some(
+            key.serialize(ValueSerializer)
+                .map_err(|e| e.at_index(0).at_index(idx))?,
+        );
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        let idx = self.entries.len();
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| ConversionError::custom("map value serialized before its key"))?;
+        let value = value
+            .serialize(ValueSerializer)
+            .map_err(|e| e.at_index(1).at_index(idx))?;
+        self.entries.push(Value::Tuple(vec![key, value]));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(Value::List(self.entries))
+    }
+}
+
+struct SerializeRecord {
+    values: Vec<Value>,
+}
+
+impl SerializeStruct for SerializeRecord {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ConversionError> {
+        self.values.push(
+            value
+                .serialize(ValueSerializer)
+                .map_err(|e| e.at_field(key))?,
+        );
+        Ok(())
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), ConversionError> {
+        Err(ConversionError::custom("skipping fields is not supported").at_field(key))
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(Value::Record(self.values))
+    }
+}
+
+// Tuple and struct variants, carrying their payload as a tuple or a record
+struct SerializeCase {
+    case_idx: u32,
+    values: Vec<Value>,
+    is_record: bool,
+}
+
+impl SerializeCase {
+    fn finish(self) -> Value {
+        let payload = if self.is_record {
+            Value::Record(self.values)
+        } else {
+            Value::Tuple(self.values)
+        };
+
+        Value::Variant {
+            case_idx: self.case_idx,
+            case_value: Some(Box::new(payload)),
+        }
+    }
+}
+
+impl SerializeTupleVariant for SerializeCase {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        let idx = self.values.len();
+        self.values.push(
+            value
+                .serialize(ValueSerializer)
+                .map_err(|e| e.at_index(idx))?,
+        );
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeStructVariant for SerializeCase {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ConversionError> {
+        self.values.push(
+            value
+                .serialize(ValueSerializer)
+                .map_err(|e| e.at_field(key))?,
+        );
+        Ok(())
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), ConversionError> {
+        Err(ConversionError::custom("skipping fields is not supported").at_field(key))
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(self.finish())
+    }
+}
+
+// Deserialization
+
+struct ValueDeserializer {
+    value: Value,
+}
+
+macro_rules! deserialize_primitive {
+    ($method:ident, $variant:ident, $visit:ident, $wit_name:literal) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+            match self.value {
+                Value::$variant(v) => visitor.$visit(v),
+                other => Err(ConversionError::type_mismatch($wit_name, &other)),
+            }
+        }
+    };
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer {
+    type Error = ConversionError;
+
+    // Only the values which describe themselves are supported, records and variants need
+    // the field and case names from the type being deserialized
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self.value {
+            Value::Bool(v) => visitor.visit_bool(v),
+            Value::U8(v) => visitor.visit_u8(v),
+            Value::U16(v) => visitor.visit_u16(v),
+            Value::U32(v) => visitor.visit_u32(v),
+            Value::U64(v) => visitor.visit_u64(v),
+            Value::S8(v) => visitor.visit_i8(v),
+            Value::S16(v) => visitor.visit_i16(v),
+            Value::S32(v) => visitor.visit_i32(v),
+            Value::S64(v) => visitor.visit_i64(v),
+            Value::F32(v) => visitor.visit_f32(v),
+            Value::F64(v) => visitor.visit_f64(v),
+            Value::Char(v) => visitor.visit_char(v),
+            Value::String(v) => visitor.visit_string(v),
+            Value::List(items) | Value::Tuple(items) => visitor.visit_seq(ValueSeq::new(items)),
+            Value::Option(None) => visitor.visit_none(),
+            Value::Option(Some(v)) => visitor.visit_some(ValueDeserializer { value: *v }),
+            other => Err(ConversionError::custom(format!(
+                "cannot deserialize a {} value without knowing its type",
+                value_kind(&other)
+            ))),
+        }
+    }
+
+    deserialize_primitive!(deserialize_bool, Bool, visit_bool, "bool");
+    deserialize_primitive!(deserialize_i8, S8, visit_i8, "s8");
+    deserialize_primitive!(deserialize_i16, S16, visit_i16, "s16");
+    deserialize_primitive!(deserialize_i32, S32, visit_i32, "s32");
+    deserialize_primitive!(deserialize_i64, S64, visit_i64, "s64");
+    deserialize_primitive!(deserialize_u8, U8, visit_u8, "u8");
+    deserialize_primitive!(deserialize_u16, U16, visit_u16, "u16");
+    deserialize_primitive!(deserialize_u32, U32, visit_u32, "u32");
+    deserialize_primitive!(deserialize_u64, U64, visit_u64, "u64");
+    deserialize_primitive!(deserialize_f32, F32, visit_f32, "f32");
+    deserialize_primitive!(deserialize_f64, F64, visit_f64, "f64");
+    deserialize_primitive!(deserialize_char, Char, visit_char, "char");
+    deserialize_primitive!(deserialize_str, String, visit_string, "string");
+    deserialize_primitive!(deserialize_string, String, visit_string, "string");
+    deserialize_primitive!(deserialize_identifier, String, visit_string, "string");
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        visitor.visit_byte_buf(Vec::<u8>::from_value(self.value)?)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self.value {
+            Value::Option(None) => visitor.visit_none(),
+            Value::Option(Some(v)) => visitor.visit_some(ValueDeserializer { value: *v }),
+            other => Err(ConversionError::type_mismatch("option", &other)),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self.value {
+            Value::Tuple(items) if items.is_empty() => visitor.visit_unit(),
+            Value::Tuple(items) => Err(ConversionError::length_mismatch(0, items.len())),
+            other => Err(ConversionError::type_mismatch("tuple", &other)),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self.value {
+            Value::List(items) => visitor.visit_seq(ValueSeq::new(items)),
+            other => Err(ConversionError::type_mismatch("list", &other)),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        match self.value {
+            Value::Tuple(items) if items.len() == len => visitor.visit_seq(ValueSeq::new(items)),
+            Value::Tuple(items) => Err(ConversionError::length_mismatch(len, items.len())),
+            other => Err(ConversionError::type_mismatch("tuple", &other)),
+        }
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self.value {
+            Value::List(entries) => visitor.visit_map(ValueEntries {
+                entries: entries.into_iter().enumerate(),
+                value: None,
+            }),
+            other => Err(ConversionError::type_mismatch("list", &other)),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        match self.value {
+            Value::Record(values) if values.len() > fields.len() => {
+                Err(ConversionError::custom(format!(
+                    "record has {} more fields than expected",
+                    values.len() - fields.len()
+                )))
+            }
+            Value::Record(values) => visitor.visit_map(ValueRecord {
+                values: fields.iter().zip(values),
+                value: None,
+            }),
+            other => Err(ConversionError::type_mismatch("record", &other)),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        match self.value {
+            Value::Variant {
+                case_idx,
+                case_value,
+            } => match variants.get(case_idx as usize) {
+                Some(variant) => visitor.visit_enum(ValueCase {
+                    case_idx,
+                    case_name: to_kebab_case(variant),
+                    case_value: case_value.map(|v| *v),
+                }),
+                None => Err(ConversionError::unknown_case(name, case_idx)),
+            },
+            other => Err(ConversionError::type_mismatch("variant", &other)),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        visitor.visit_unit()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct ValueSeq {
+    items: std::iter::Enumerate<std::vec::IntoIter<Value>>,
+}
+
+impl ValueSeq {
+    fn new(items: Vec<Value>) -> Self {
+        ValueSeq {
+            items: items.into_iter().enumerate(),
+        }
+    }
+}
+
+impl<'de> SeqAccess<'de> for ValueSeq {
+    type Error = ConversionError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, ConversionError> {
+        match self.items.next() {
+            Some((idx, value)) => seed
+                .deserialize(ValueDeserializer { value })
+                .map(Some)
+                .map_err(|e| e.at_index(idx)),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+// Map entries are `tuple<k, v>` values
+struct ValueEntries {
+    entries: std::iter::Enumerate<std::vec::IntoIter<Value>>,
+    value: Option<(usize, Value)>,
+}
+
+impl<'de> MapAccess<'de> for ValueEntries {
+    type Error = ConversionError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ConversionError> {
+        match self.entries.next() {
+            Some((idx, Value::Tuple(kv))) if kv.len() == 2 => {
+                let mut kv = kv.into_iter();
+                let key = kv.next().unwrap();
+                self.value = Some((idx, kv.next().unwrap()));
+
+                seed.deserialize(ValueDeserializer { value: key })
+                    .map(Some)
+                    .map_err(|e| e.at_index(0).at_index(idx))
+            }
+            Some((idx, Value::Tuple(kv))) => {
+                Err(ConversionError::length_mismatch(2, kv.len()).at_index(idx))
+            }
+            Some((idx, other)) => {
+                Err(ConversionError::type_mismatch("tuple", &other).at_index(idx))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, ConversionError> {
+        let (idx, value) = self
+            .value
+            .take()
+            .ok_or_else(|| ConversionError::custom("map value requested before its key"))?;
+
+        seed.deserialize(ValueDeserializer { value })
+            .map_err(|e| e.at_index(1).at_index(idx))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+// Record fields are positional, their names come from the struct being deserialized
+struct ValueRecord {
+    values: std::iter::Zip<std::slice::Iter<'static, &'static str>, std::vec::IntoIter<Value>>,
+    value: Option<(&'static str, Value)>,
+}
+
+impl<'de> MapAccess<'de> for ValueRecord {
+    type Error = ConversionError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ConversionError> {
+        match self.values.next() {
+            Some((name, value)) => {
+                self.value = Some((*name, value));
+                seed.deserialize(name.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, ConversionError> {
+        let (name, value) = self
+            .value
+            .take()
+            .ok_or_else(|| ConversionError::custom("field value requested before its name"))?;
+
+        seed.deserialize(ValueDeserializer { value })
+            .map_err(|e| e.at_field(name))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
+
+struct ValueCase {
+    case_idx: u32,
+    case_name: String,
+    case_value: Option<Value>,
+}
+
+impl<'de> EnumAccess<'de> for ValueCase {
+    type Error = ConversionError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), ConversionError> {
+        let variant = seed.deserialize(self.case_idx.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl ValueCase {
+    fn payload(self) -> Result<(String, ValueDeserializer), ConversionError> {
+        match self.case_value {
+            Some(value) => Ok((self.case_name, ValueDeserializer { value })),
+            None => Err(ConversionError::custom("missing payload").at_field(self.case_name)),
+        }
+    }
+}
+
+impl<'de> VariantAccess<'de> for ValueCase {
+    type Error = ConversionError;
+
+    fn unit_variant(self) -> Result<(), ConversionError> {
+        match self.case_value {
+            None => Ok(()),
+            Some(_) => Err(ConversionError::custom("unexpected payload").at_field(self.case_name)),
+        }
+    }
+
+    // A case without payload is accepted if the payload is a unit, like the `()` of `Ok(())`
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, ConversionError> {
+        let case_value = self.case_value.unwrap_or(Value::Tuple(vec![]));
+        seed.deserialize(ValueDeserializer { value: case_value })
+            .map_err(|e| e.at_field(self.case_name))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        let (case_name, payload) = self.payload()?;
+        payload
+            .deserialize_tuple(len, visitor)
+            .map_err(|e| e.at_field(case_name))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        let (case_name, payload) = self.payload()?;
+        payload
+            .deserialize_struct("", fields, visitor)
+            .map_err(|e| e.at_field(case_name))
+    }
+}
+
+// Type inference
+
+const MAX_TRACE_PASSES: usize = 10_000;
+
+// Recursion is detected through the names of the types being traced,
+// this is only a safety net for anything nested too deeply anyway
+const MAX_TRACE_DEPTH: usize = 64;
+
+// The enums seen so far, keyed by their position in the type. Positions include the
+// variants chosen for the enclosing enums, so every occurrence of an enum is traced separately.
+#[derive(Default)]
+struct TraceState {
+    enums: HashMap<String, TracedEnum>,
+    // Names of the structs and enums enclosing the current position
+    names: Vec<&'static str>,
+}
+
+struct TracedEnum {
+    variants: &'static [&'static str],
+    // `None` for the variants not visited yet, otherwise the payload type of the variant
+    cases: Vec<Option<Option<AnalysedType>>>,
+}
+
+impl TraceState {
+    // WIT has no recursive types, so a type nested in a type with the same name is rejected.
+    // Note this also rejects generic types nested in other instances of themselves.
+    fn enter(&mut self, name: &'static str) -> Result<(), ConversionError> {
+        if self.names.contains(&name) {
+            return Err(ConversionError::custom(format!(
+                "recursive types are not supported, {} contains itself",
+                name
+            )));
+        }
+
+        self.names.push(name);
+        Ok(())
+    }
+
+    fn exit(&mut self) {
+        self.names.pop();
+    }
+
+    fn has_incomplete_enums_under(&self, path: &str) -> bool {
+        self.enums
+            .iter()
+            .any(|(key, e)| key.starts_with(path) && e.cases.iter().any(Option::is_none))
+    }
+
+    // Prefers variants not visited yet, then variants leading to enums with variants not visited yet
+    fn choose_variant(&self, path: &str) -> usize {
+        let traced = &self.enums[path];
+
+        traced
+            .cases
+            .iter()
+            .position(Option::is_none)
+            .or_else(|| {
+                (0..traced.cases.len())
+                    .find(|idx| self.has_incomplete_enums_under(&variant_path(path, *idx)))
+            })
+            .unwrap_or(0)
+    }
+}
+
+fn variant_path(path: &str, idx: usize) -> String {
+    format!("{}<{}>", path, idx)
+}
+
+// Records the WIT type of whatever the type being traced asks to deserialize into `out`
+struct Tracer<'a> {
+    state: &'a mut TraceState,
+    path: String,
+    depth: usize,
+    out: &'a mut Option<AnalysedType>,
+}
+
+impl<'a> Tracer<'a> {
+    fn nested<'b>(
+        state: &'b mut TraceState,
+        path: String,
+        depth: usize,
+        out: &'b mut Option<AnalysedType>,
+    ) -> Result<Tracer<'b>, ConversionError> {
+        if depth >= MAX_TRACE_DEPTH {
+            return Err(ConversionError::custom(
+                "the type is recursive or nested too deeply",
+            ));
+        }
+
+        Ok(Tracer {
+            state,
+            path,
+            depth: depth + 1,
+            out,
+        })
+    }
+
+    fn trace_seed<'de, T: DeserializeSeed<'de>>(
+        state: &mut TraceState,
+        path: String,
+        depth: usize,
+        seed: T,
+    ) -> Result<(T::Value, AnalysedType), ConversionError> {
+        let mut typ = None;
+        let value = seed.deserialize(Tracer::nested(state, path, depth, &mut typ)?)?;
+        let typ = typ.ok_or_else(no_wit_type)?;
+        Ok((value, typ))
+    }
+
+    // `name` is the name of the tuple struct, if any
+    fn trace_tuple<'de, V: Visitor<'de>>(
+        self,
+        name: Option<&'static str>,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        if let Some(name) = name {
+            self.state.enter(name)?;
+        }
+
+        let mut access = TraceSeq {
+            state: self.state,
+            path: self.path,
+            depth: self.depth,
+            remaining: len,
+            is_tuple: true,
+            types: Vec::with_capacity(len),
+        };
+
+        let value = visitor.visit_seq(&mut access)?;
+        if name.is_some() {
+            access.state.exit();
+        }
+
+        *self.out = Some(analysed_type::tuple(access.types));
+        Ok(value)
+    }
+
+    // `name` is the name of the struct, struct variants have no name of their own
+    fn trace_record<'de, V: Visitor<'de>>(
+        self,
+        name: Option<&'static str>,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        if let Some(name) = name {
+            self.state.enter(name)?;
+        }
+
+        let mut access = TraceRecord {
+            state: self.state,
+            path: self.path,
+            depth: self.depth,
+            fields: fields.iter(),
+            current: None,
+            types: Vec::with_capacity(fields.len()),
+        };
+
+        let value = visitor.visit_map(&mut access)?;
+        if name.is_some() {
+            access.state.exit();
+        }
+
+        *self.out = Some(analysed_type::record(access.types));
+        Ok(value)
+    }
+}
+
+macro_rules! trace_primitive {
+    ($method:ident, $visit:ident, $placeholder:expr, $wit_type:expr) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+            *self.out = Some($wit_type);
+            visitor.$visit($placeholder)
+        }
+    };
+}
+
+impl<'de> Deserializer<'de> for Tracer<'_> {
+    type Error = ConversionError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, ConversionError> {
+        Err(ConversionError::custom(
+            "the WIT type cannot be inferred for types deserialized with `deserialize_any`",
+        ))
+    }
+
+    trace_primitive!(deserialize_bool, visit_bool, false, analysed_type::bool());
+    trace_primitive!(deserialize_i8, visit_i8, 1, analysed_type::s8());
+    trace_primitive!(deserialize_i16, visit_i16, 1, analysed_type::s16());
+    trace_primitive!(deserialize_i32, visit_i32, 1, analysed_type::s32());
+    trace_primitive!(deserialize_i64, visit_i64, 1, analysed_type::s64());
+    trace_primitive!(deserialize_u8, visit_u8, 1, analysed_type::u8());
+    trace_primitive!(deserialize_u16, visit_u16, 1, analysed_type::u16());
+    trace_primitive!(deserialize_u32, visit_u32, 1, analysed_type::u32());
+    trace_primitive!(deserialize_u64, visit_u64, 1, analysed_type::u64());
+    trace_primitive!(deserialize_f32, visit_f32, 1.0, analysed_type::f32());
+    trace_primitive!(deserialize_f64, visit_f64, 1.0, analysed_type::f64());
+    trace_primitive!(deserialize_char, visit_char, 'a', analysed_type::chr());
+    trace_primitive!(
+        deserialize_str,
+        visit_string,
+        String::new(),
+        analysed_type::str()
+    );
+    trace_primitive!(
+        deserialize_string,
+        visit_string,
+        String::new(),
+        analysed_type::str()
+    );
+    trace_primitive!(
+        deserialize_bytes,
+        visit_byte_buf,
+        Vec::new(),
+        analysed_type::list(analysed_type::u8())
+    );
+    trace_primitive!(
+        deserialize_byte_buf,
+        visit_byte_buf,
+        Vec::new(),
+        analysed_type::list(analysed_type::u8())
+    );
+
+    // Leaves the type unset, which is only accepted for the payload of a newtype variant
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        let mut inner = None;
+        let value = visitor.visit_some(Tracer::nested(
+            self.state,
+            format!("{}?", self.path),
+            self.depth,
+            &mut inner,
+        )?)?;
+
+        let inner = inner.ok_or_else(no_wit_type)?;
+        *self.out = Some(analysed_type::option(inner));
+        Ok(value)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        self.state.enter(name)?;
+        let value = visitor.visit_newtype_struct(Tracer::nested(
+            &mut *self.state,
+            self.path,
+            self.depth,
+            self.out,
+        )?)?;
+        self.state.exit();
+        Ok(value)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        let mut access = TraceSeq {
+            state: self.state,
+            path: self.path,
+            depth: self.depth,
+            remaining: 1,
+            is_tuple: false,
+            types: Vec::with_capacity(1),
+        };
+
+        let value = visitor.visit_seq(&mut access)?;
+        let element = access
+            .types
+            .pop()
+            .ok_or_else(|| ConversionError::custom("could not infer the list element type"))?;
+        *self.out = Some(analysed_type::list(element));
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        self.trace_tuple(None, len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        self.trace_tuple(Some(name), len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        let mut access = TraceEntry {
+            state: self.state,
+            path: self.path,
+            depth: self.depth,
+            key: None,
+            value: None,
+            done: false,
+        };
+
+        let value = visitor.visit_map(&mut access)?;
+        match (access.key, access.value) {
+            (Some(key), Some(value_type)) => {
+                *self.out = Some(analysed_type::list(analysed_type::tuple(vec![
+                    key, value_type,
+                ])));
+                Ok(value)
+            }
+            _ => Err(ConversionError::custom(
+                "could not infer the map key and value types",
+            )),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        self.trace_record(Some(name), fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        if variants.is_empty() {
+            return Err(ConversionError::custom(
+                "enums without variants are not supported",
+            ));
+        }
+
+        let path = self.path;
+        let state = self.state;
+        state.enter(name)?;
+
+        state
+            .enums
+            .entry(path.clone())
+            .or_insert_with(|| TracedEnum {
+                variants,
+                cases: vec![None; variants.len()],
+            });
+
+        let chosen = state.choose_variant(&path);
+        let mut payload = None;
+
+        let value = visitor.visit_enum(TraceCase {
+            state: &mut *state,
+            path: variant_path(&path, chosen),
+            depth: self.depth,
+            case_idx: chosen as u32,
+            payload: &mut payload,
+        })?;
+
+        let payload = payload.ok_or_else(|| {
+            ConversionError::custom("could not infer the WIT type of the enum variant")
+        })?;
+        state.exit();
+
+        let traced = state.enums.get_mut(&path).unwrap();
+        traced.cases[chosen] = Some(payload);
+
+        // Variants not visited yet are completed in the following passes
+        let cases = traced
+            .variants
+            .iter()
+            .zip(&traced.cases)
+            .map(|(name, case)| match case {
+                Some(Some(typ)) => analysed_type::case(&to_kebab_case(name), typ.clone()),
+                _ => analysed_type::unit_case(&to_kebab_case(name)),
+            })
+            .collect();
+
+        *self.out = Some(analysed_type::variant(cases));
+        Ok(value)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        Err(ConversionError::custom(
+            "the WIT type cannot be inferred for identifiers",
+        ))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        visitor.visit_unit()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+// Sequences yield a single element, tuples yield all of their elements
+struct TraceSeq<'a> {
+    state: &'a mut TraceState,
+    path: String,
+    depth: usize,
+    remaining: usize,
+    is_tuple: bool,
+    types: Vec<AnalysedType>,
+}
+
+impl<'de> SeqAccess<'de> for TraceSeq<'_> {
+    type Error = ConversionError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, ConversionError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+
+        let path = if self.is_tuple {
+            format!("{}.{}", self.path, self.types.len())
+        } else {
+            format!("{}[]", self.path)
+        };
+
+        let (value, typ) = Tracer::trace_seed(self.state, path, self.depth, seed)?;
+        self.types.push(typ);
+        Ok(Some(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+// Maps yield a single entry
+struct TraceEntry<'a> {
+    state: &'a mut TraceState,
+    path: String,
+    depth: usize,
+    key: Option<AnalysedType>,
+    value: Option<AnalysedType>,
+    done: bool,
+}
+
+impl<'de> MapAccess<'de> for TraceEntry<'_> {
+    type Error = ConversionError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ConversionError> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+
+        let (key, typ) =
+            Tracer::trace_seed(self.state, format!("{}{{k}}", self.path), self.depth, seed)?;
+        self.key = Some(typ);
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, ConversionError> {
+        let (value, typ) =
+            Tracer::trace_seed(self.state, format!("{}{{v}}", self.path), self.depth, seed)?;
+        self.value = Some(typ);
+        Ok(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(if self.done { 0 } else { 1 })
+    }
+}
+
+struct TraceRecord<'a> {
+    state: &'a mut TraceState,
+    path: String,
+    depth: usize,
+    fields: std::slice::Iter<'static, &'static str>,
+    current: Option<&'static str>,
+    types: Vec<NameTypePair>,
+}
+
+impl<'de> MapAccess<'de> for TraceRecord<'_> {
+    type Error = ConversionError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ConversionError> {
+        match self.fields.next() {
+            Some(name) => {
+                self.current = Some(name);
+                seed.deserialize(name.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, ConversionError> {
+        let name = self
+            .current
+            .take()
+            .ok_or_else(|| ConversionError::custom("field value requested before its name"))?;
+
+        let (value, typ) = Tracer::trace_seed(
+            self.state,
+            format!("{}.{}", self.path, name),
+            self.depth,
+            seed,
+        )
+        .map_err(|e| e.at_field(name))?;
+
+        self.types.push(NameTypePair {
+            name: name.to_string(),
+            typ,
+        });
+        Ok(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+struct TraceCase<'a> {
+    state: &'a mut TraceState,
+    path: String,
+    depth: usize,
+    case_idx: u32,
+    payload: &'a mut Option<Option<AnalysedType>>,
+}
+
+impl<'de, 'a> EnumAccess<'de> for TraceCase<'a> {
+    type Error = ConversionError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), ConversionError> {
+        let variant = seed.deserialize(self.case_idx.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for TraceCase<'_> {
+    type Error = ConversionError;
+
+    fn unit_variant(self) -> Result<(), ConversionError> {
+        *self.payload = Some(None);
+        Ok(())
+    }
+
+    // A unit payload leaves the type unset, and makes a case without payload
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, ConversionError> {
+        let mut typ = None;
+        let value =
+            seed.deserialize(Tracer::nested(self.state, self.path, self.depth, &mut typ)?)?;
+        *self.payload = Some(typ);
+        Ok(value)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        let mut typ = None;
+        let value = Tracer::nested(self.state, self.path, self.depth, &mut typ)?
+            .trace_tuple(None, len, visitor)?;
+        *self.payload = Some(typ);
+        Ok(value)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        let mut typ = None;
+        let value = Tracer::nested(self.state, self.path, self.depth, &mut typ)?
+            .trace_record(None, fields, visitor)?;
+        *self.payload = Some(typ);
+        Ok(value)
+    }
+}
+
+// WIT case names are kebab-case, like the cases of enums deriving `AgentArg`
+fn to_kebab_case(s: &str) -> String {
+    let mut result = String::new();
+
+    for (i, c) in s.chars().enumerate() {
+        if c == '_' {
+            result.push('-');
+        } else if c.is_uppercase() {
+            if i != 0 {
+                result.push('-');
+            }
+            result.push(c.to_ascii_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::validate_value;
+    use golem_wasm_ast::analysis::analysed_type::{
+        case, field, list, option, record, str, tuple, u32, u8, unit_case, variant,
+    };
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+    use std::fmt::Debug;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Order {
+        id: u32,
+        note: Option<String>,
+        lines: Vec<Line>,
+        tags: BTreeMap<String, u8>,
+        status: Status,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "kebab-case")]
+    struct Line {
+        sku: Sku,
+        unit_count: u32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Sku(String);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Status {
+        Open,
+        Shipped(u32, String),
+        Cancelled { reason: String },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum Untagged {
+        Number(u32),
+        Text(String),
+    }
+
+    // Converts the value back and forth, checking that the value matches the inferred type
+    fn round_trip<T: Serialize + DeserializeOwned + Debug + PartialEq>(value: T) -> Value {
+        let typ = wit_type::<T>().unwrap();
+        let converted = to_value(&value).unwrap();
+
+        validate_value(&converted, &typ).unwrap();
+        assert_eq!(from_value::<T>(converted.clone()).unwrap(), value);
+
+        converted
+    }
+
+    #[test]
+    fn infers_the_wit_type_of_nested_types() {
+        let expected = record(vec![
+            field("id", u32()),
+            field("note", option(str())),
+            field(
+                "lines",
+                list(record(vec![
+                    field("sku", str()),
+                    field("unit-count", u32()),
+                ])),
+            ),
+            field("tags", list(tuple(vec![str(), u8()]))),
+            field(
+                "status",
+                variant(vec![
+                    unit_case("open"),
+                    case("shipped", tuple(vec![u32(), str()])),
+                    case("cancelled", record(vec![field("reason", str())])),
+                ]),
+            ),
+        ]);
+
+        assert_eq!(wit_type::<Order>().unwrap(), expected);
+    }
+
+    #[test]
+    fn unit_payloads_are_absent() {
+        assert_eq!(
+            wit_type::<Result<(), String>>().unwrap(),
+            variant(vec![unit_case("ok"), case("err", str())])
+        );
+        assert_eq!(
+            round_trip(Ok::<(), String>(())),
+            Value::Variant {
+                case_idx: 0,
+                case_value: None,
+            }
+        );
+        round_trip(Err::<(), String>("failed".to_string()));
+    }
+
+    #[test]
+    fn units_outside_of_variant_payloads_are_rejected() {
+        assert!(wit_type::<()>().is_err());
+        assert!(wit_type::<(u8, ())>().is_err());
+        assert!(wit_type::<Option<()>>().is_err());
+    }
+
+    #[test]
+    fn round_trips_records_and_variants() {
+        for status in [
+            Status::Open,
+            Status::Shipped(3, "parcel".to_string()),
+            Status::Cancelled {
+                reason: "duplicate".to_string(),
+            },
+        ] {
+            round_trip(Order {
+                id: 1,
+                note: Some("fragile".to_string()),
+                lines: vec![Line {
+                    sku: Sku("a-1".to_string()),
+                    unit_count: 2,
+                }],
+                tags: BTreeMap::from([("gift".to_string(), 1)]),
+                status,
+            });
+        }
+    }
+
+    #[test]
+    fn round_trips_primitives_and_containers() {
+        round_trip(42u64);
+        round_trip(-7i16);
+        round_trip(1.5f64);
+        round_trip('x');
+        round_trip(true);
+        round_trip("text".to_string());
+        round_trip(Some(vec![(1u8, "a".to_string())]));
+        round_trip(Option::<u32>::None);
+
+        assert_eq!(
+            round_trip(Sku("b".to_string())),
+            Value::String("b".to_string())
+        );
+    }
+
+    #[test]
+    fn serde_wrapper_uses_the_same_mapping() {
+        let value = Serde(Sku("c".to_string()));
+
+        assert_eq!(ToValue::to_value(&value), Value::String("c".to_string()));
+        assert_eq!(
+            <Serde<Sku> as FromValue>::from_value(Value::String("c".to_string())).unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn rejects_values_of_the_wrong_shape() {
+        let error =
+            from_value::<Line>(Value::Record(vec![Value::String("a".to_string())])).unwrap_err();
+
+        assert!(error.to_string().contains("unit-count"), "{}", error);
+        assert!(from_value::<u32>(Value::String("1".to_string())).is_err());
+    }
+
+    #[test]
+    fn rejects_untagged_enums() {
+        assert!(wit_type::<Untagged>().is_err());
+    }
+}
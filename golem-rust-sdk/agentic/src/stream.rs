@@ -0,0 +1,93 @@
+// A stream of values produced by an agent method, such as the tokens generated by an LLM.
+//
+// The stream is pulled by the caller one chunk at a time: `invoke` returns the first chunk as an
+// `emit` status update, and every call of `get-next` on the agent produces the next one, until
+// `completed` marks the end of the stream. Chunks are only produced when they are pulled, so the
+// agent does not run ahead of the caller. Invoking another method of the agent abandons the stream.
+//
+// The generated remote clients return a stream of the chunks too, pulling each of them from the
+// remote agent as the caller consumes it.
+//
+// Example usage:
+//
+// ```
+//  [agent_definition]
+//  trait WriterAgent {
+//    fn write(&self, topic: String) -> AgentStream<String>;
+//  }
+// ```
+
+use crate::bindings::golem::agent::common::StatusUpdate;
+use crate::type_mapping::ToWitValue;
+use golem_wasm_rpc::WitValue;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+thread_local! {
+    // The remaining chunks of the streams being pulled, by the id of the agent producing them
+    static PENDING: RefCell<HashMap<String, Box<dyn Iterator<Item = WitValue>>>> =
+        RefCell::new(HashMap::new());
+}
+
+pub struct AgentStream<T> {
+    chunks: Box<dyn Iterator<Item = T>>,
+}
+
+impl<T> AgentStream<T> {
+    pub fn new(chunks: impl IntoIterator<Item = T> + 'static) -> Self
+    where
+        T: 'static,
+    {
+        AgentStream {
+            chunks: Box::new(chunks.into_iter()),
+        }
+    }
+}
+
+impl<T> Iterator for AgentStream<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.chunks.next()
+    }
+}
+
+impl<T: 'static> FromIterator<T> for AgentStream<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        AgentStream::new(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+// Makes `stream` the stream being pulled from the agent with `agent_id`, and returns its first
+// status update. Used by the code generated for methods returning an `AgentStream`.
+pub fn open<T: ToWitValue + 'static>(agent_id: String, stream: AgentStream<T>) -> StatusUpdate {
+    let chunks = stream.map(|chunk| chunk.to_wit_value());
+
+    PENDING.with(|pending| {
+        pending
+            .borrow_mut()
+            .insert(agent_id.clone(), Box::new(chunks))
+    });
+
+    next(&agent_id).unwrap_or(StatusUpdate::Completed)
+}
+
+// Produces the next chunk of the stream being pulled from the agent with `agent_id`, or
+// `completed` once it is exhausted. `None` if no stream is being pulled from the agent.
+pub(crate) fn next(agent_id: &str) -> Option<StatusUpdate> {
+    // The chunks are taken out while producing the next one, which runs the code of the agent
+    let mut chunks = PENDING.with(|pending| pending.borrow_mut().remove(agent_id))?;
+
+    match chunks.next() {
+        Some(chunk) => {
+            PENDING.with(|pending| pending.borrow_mut().insert(agent_id.to_string(), chunks));
+            Some(StatusUpdate::Emit(chunk))
+        }
+        None => Some(StatusUpdate::Completed),
+    }
+}
+
+// Abandons the stream being pulled from the agent with `agent_id`, if any
+pub(crate) fn close(agent_id: &str) {
+    PENDING.with(|pending| pending.borrow_mut().remove(agent_id));
+}
@@ -1,15 +1,20 @@
-use golem_wasm_ast::analysis::analysed_type::{str, u32, u64};
-use golem_wasm_ast::analysis::AnalysedType;
+use golem_wasm_ast::analysis::analysed_type;
+use golem_wasm_ast::analysis::analysed_type::str;
 use golem_wasm_rpc::{NodeBuilder, WitValue};
 use golem_wasm_rpc::{Value, WitType};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::Arc;
 use crate::agent::Agent;
+use crate::conversion_error::ConversionError;
 
-pub trait AgentArg: ToValue + FromWitValue + ToWitType {
+pub trait AgentArg: ToValue + FromValue + ToWitType {
     fn to_value(&self) -> golem_wasm_rpc::Value {
         ToValue::to_value(self)
     }
 
-    fn from_wit_value(value: WitValue) -> Result<Self, String>
+    fn from_wit_value(value: WitValue) -> Result<Self, ConversionError>
     where
         Self: Sized,
     {
@@ -24,7 +29,18 @@ pub trait AgentArg: ToValue + FromWitValue + ToWitType {
     }
 }
 
-impl<T: ToValue + FromWitValue + ToWitType> AgentArg for T {}
+impl<T: ToValue + FromValue + ToWitType> AgentArg for T {}
+
+// Implemented by the derived `AgentArg` of structs with named fields, whose values are WIT records.
+// Only these can be inlined into another record with `#[agent_arg(flatten)]`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be flattened, as it is not a struct with named fields deriving `AgentArg`"
+)]
+pub trait AgentArgRecord {}
+
+// Used by the derived code to reject flattening other types at compile time
+#[doc(hidden)]
+pub fn assert_agent_arg_record<T: AgentArgRecord + ?Sized>() {}
 
 pub trait ToValue {
     fn to_value(&self) -> golem_wasm_rpc::Value;
@@ -32,7 +48,7 @@ pub trait ToValue {
 
 
 pub trait FromValue {
-    fn from_value(value: golem_wasm_rpc::Value) -> Result<Self, String>
+    fn from_value(value: golem_wasm_rpc::Value) -> Result<Self, ConversionError>
     where
         Self: Sized;
 }
@@ -43,56 +59,17 @@ impl ToValue for String {
     }
 }
 
-impl ToValue for u32 {
-    fn to_value(&self) -> Value {
-        golem_wasm_rpc::Value::U32(*self)
-    }
-}
-
-impl ToValue for bool {
-    fn to_value(&self) -> Value {
-        golem_wasm_rpc::Value::Bool(*self)
-    }
-}
-
-impl ToValue for u64 {
-    fn to_value(&self) -> Value {
-        golem_wasm_rpc::Value::U64(*self)
+impl ToValue for str {
+    fn to_value(&self) -> golem_wasm_rpc::Value {
+        golem_wasm_rpc::Value::String(self.to_string())
     }
 }
 
 impl FromValue for String {
-    fn from_value(value: golem_wasm_rpc::Value) -> Result<Self, String> {
+    fn from_value(value: golem_wasm_rpc::Value) -> Result<Self, ConversionError> {
         match value {
             golem_wasm_rpc::Value::String(s) => Ok(s),
-            _ => Err("Expected a String value".to_string()),
-        }
-    }
-}
-
-impl FromValue for u32 {
-    fn from_value(value: golem_wasm_rpc::Value) -> Result<Self, String> {
-        match value {
-            golem_wasm_rpc::Value::U32(n) => Ok(n),
-            _ => Err("Expected a u32 value".to_string()),
-        }
-    }
-}
-
-impl FromValue for bool {
-    fn from_value(value: golem_wasm_rpc::Value) -> Result<Self, String> {
-        match value {
-            golem_wasm_rpc::Value::Bool(b) => Ok(b),
-            _ => Err("Expected a bool value".to_string()),
-        }
-    }
-}
-
-impl FromValue for u64 {
-    fn from_value(value: golem_wasm_rpc::Value) -> Result<Self, String> {
-        match value {
-            golem_wasm_rpc::Value::U64(n) => Ok(n),
-            _ => Err("Expected a u64 value".to_string()),
+            other => Err(ConversionError::type_mismatch("string", &other)),
         }
     }
 }
@@ -119,132 +96,501 @@ impl ToWitType for String {
     }
 }
 
-impl ToWitType for u32 {
-    fn get_wit_type() -> WitType {
-        let analysed_type: AnalysedType = u32();
-        WitType::from(analysed_type)
-    }
-}
-
-impl ToWitType for u64 {
+impl ToWitType for str {
     fn get_wit_type() -> WitType {
-        let analysed_type = u64();
+        let analysed_type = str();
         WitType::from(analysed_type)
     }
 }
 
+// `FromValue` is the only decoding trait to implement, decoding a `WitValue` is derived from it.
+// This way nested types (fields of records, elements of lists etc) and top level arguments
+// are decoded the same way.
 pub trait FromWitValue {
-    fn from_wit_value(value: WitValue) -> Result<Self, String>
+    fn from_wit_value(value: WitValue) -> Result<Self, ConversionError>
     where
         Self: Sized;
 }
 
-impl FromWitValue for String {
-    fn from_wit_value(value: WitValue) -> Result<Self, String> {
-        let value = golem_wasm_rpc::Value::from(value);
+impl<T: FromValue> FromWitValue for T {
+    fn from_wit_value(value: WitValue) -> Result<Self, ConversionError> {
+        T::from_value(golem_wasm_rpc::Value::from(value))
+    }
+}
 
-        match value {
-            golem_wasm_rpc::Value::String(s) => Ok(s),
-            _ => Err("Expected a String WitValue".to_string()),
+// Every WIT primitive apart from `string` maps one to one to a `Copy` Rust type,
+// hence the implementations of all four conversion traits are generated together.
+macro_rules! impl_primitive {
+    ($ty:ty, $variant:ident, $wit_name:literal, $wit_type:expr) => {
+        impl ToValue for $ty {
+            fn to_value(&self) -> Value {
+                golem_wasm_rpc::Value::$variant(*self)
+            }
+        }
+
+        impl FromValue for $ty {
+            fn from_value(value: golem_wasm_rpc::Value) -> Result<Self, ConversionError> {
+                match value {
+                    golem_wasm_rpc::Value::$variant(v) => Ok(v),
+                    other => Err(ConversionError::type_mismatch($wit_name, &other)),
+                }
+            }
+        }
+
+        impl ToWitType for $ty {
+            fn get_wit_type() -> WitType {
+                WitType::from($wit_type)
+            }
         }
+    };
+}
+
+impl_primitive!(bool, Bool, "bool", analysed_type::bool());
+impl_primitive!(u8, U8, "u8", analysed_type::u8());
+impl_primitive!(u16, U16, "u16", analysed_type::u16());
+impl_primitive!(u32, U32, "u32", analysed_type::u32());
+impl_primitive!(u64, U64, "u64", analysed_type::u64());
+impl_primitive!(i8, S8, "s8", analysed_type::s8());
+impl_primitive!(i16, S16, "s16", analysed_type::s16());
+impl_primitive!(i32, S32, "s32", analysed_type::s32());
+impl_primitive!(i64, S64, "s64", analysed_type::s64());
+impl_primitive!(f32, F32, "f32", analysed_type::f32());
+impl_primitive!(f64, F64, "f64", analysed_type::f64());
+impl_primitive!(char, Char, "char", analysed_type::chr());
+
+impl FromValue for WitValue {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        Ok(WitValue::from(value))
     }
 }
 
-impl FromWitValue for u32 {
-    fn from_wit_value(value: WitValue) -> Result<Self, String>
-    where
-        Self: Sized,
-    {
-        let value = golem_wasm_rpc::Value::from(value);
+impl FromValue for golem_wasm_rpc::Value {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        Ok(value)
+    }
+}
+
+// Containers
+
+impl<T: ToValue> ToValue for Vec<T> {
+    fn to_value(&self) -> Value {
+        golem_wasm_rpc::Value::List(self.iter().map(ToValue::to_value).collect())
+    }
+}
 
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
         match value {
-            golem_wasm_rpc::Value::U32(n) => Ok(n),
-            _ => Err("Expected a u32 WitValue".to_string()),
+            golem_wasm_rpc::Value::List(list) => decode_list(list),
+            other => Err(ConversionError::type_mismatch("list", &other)),
         }
     }
 }
 
-impl FromWitValue for bool {
-    fn from_wit_value(value: WitValue) -> Result<Self, String>
-    where
-        Self: Sized,
-    {
-        let value = golem_wasm_rpc::Value::from(value);
+impl<T: ToWitType> ToWitType for Vec<T> {
+    fn get_wit_type() -> WitType {
+        WitType::from(analysed_type::list(wit_type_of::<T>()))
+    }
+}
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self) -> Value {
+        golem_wasm_rpc::Value::Option(self.as_ref().map(|v| Box::new(v.to_value())))
+    }
+}
 
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
         match value {
-            golem_wasm_rpc::Value::Bool(b) => Ok(b),
-            _ => Err("Expected a bool WitValue".to_string()),
+            golem_wasm_rpc::Value::Option(Some(v)) => T::from_value(*v).map(Some),
+            golem_wasm_rpc::Value::Option(None) => Ok(None),
+            other => Err(ConversionError::type_mismatch("option", &other)),
         }
     }
 }
 
-impl FromWitValue for u64 {
-    fn from_wit_value(value: WitValue) -> Result<Self, String>
-    where
-        Self: Sized,
-    {
-        let value = golem_wasm_rpc::Value::from(value);
+impl<T: ToWitType> ToWitType for Option<T> {
+    fn get_wit_type() -> WitType {
+        WitType::from(analysed_type::option(wit_type_of::<T>()))
+    }
+}
 
-        match value {
-            golem_wasm_rpc::Value::U64(n) => Ok(n),
-            _ => Err("Expected a u32 WitValue".to_string()),
+// The `ok` or `err` payload of a `Result`. `()` has no counterpart in WIT, so it can only be the
+// absent payload of a case, e.g. `Result<(), E>` maps to `result<_, e>` with `Ok(())` as `ok`
+// without a payload. Every other payload is a value of its own type.
+pub trait ResultPayload: Sized {
+    fn to_payload(&self) -> Option<Value>;
+
+    fn from_payload(payload: Option<Value>) -> Result<Self, ConversionError>;
+
+    fn payload_type() -> Option<golem_wasm_ast::analysis::AnalysedType>;
+}
+
+impl<T: ToValue + FromValue + ToWitType> ResultPayload for T {
+    fn to_payload(&self) -> Option<Value> {
+        Some(self.to_value())
+    }
+
+    fn from_payload(payload: Option<Value>) -> Result<Self, ConversionError> {
+        match payload {
+            Some(value) => T::from_value(value),
+            None => Err(ConversionError::custom("missing payload")),
         }
     }
+
+    fn payload_type() -> Option<golem_wasm_ast::analysis::AnalysedType> {
+        Some(wit_type_of::<T>())
+    }
 }
 
-impl FromWitValue for Vec<WitValue> {
-    fn from_wit_value(value: WitValue) -> Result<Self, String>
-    where
-        Self: Sized,
-    {
-        let value = golem_wasm_rpc::Value::from(value);
+impl ResultPayload for () {
+    fn to_payload(&self) -> Option<Value> {
+        None
+    }
 
-        match value {
-            golem_wasm_rpc::Value::List(list) => Ok(list.into_iter().map(WitValue::from).collect()),
-            _ => Err("Expected a List WitValue".to_string()),
+    fn from_payload(payload: Option<Value>) -> Result<Self, ConversionError> {
+        match payload {
+            None => Ok(()),
+            Some(_) => Err(ConversionError::custom("unexpected payload")),
         }
     }
+
+    fn payload_type() -> Option<golem_wasm_ast::analysis::AnalysedType> {
+        None
+    }
 }
 
-impl<T: FromWitValue> FromWitValue for Vec<T> {
-    fn from_wit_value(value: WitValue) -> Result<Self, String>
-    where
-        Self: Sized,
-    {
-        let value = golem_wasm_rpc::Value::from(value);
+impl<T: ResultPayload, E: ResultPayload> ToValue for Result<T, E> {
+    fn to_value(&self) -> Value {
+        match self {
+            Ok(v) => golem_wasm_rpc::Value::Result(Ok(v.to_payload().map(Box::new))),
+            Err(e) => golem_wasm_rpc::Value::Result(Err(e.to_payload().map(Box::new))),
+        }
+    }
+}
 
+impl<T: ResultPayload, E: ResultPayload> FromValue for Result<T, E> {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
         match value {
-            golem_wasm_rpc::Value::List(list) => list
-                .into_iter()
-                .map(|v| T::from_wit_value(WitValue::from(v)))
-                .collect(),
-            _ => Err("Expected a List WitValue".to_string()),
+            golem_wasm_rpc::Value::Result(Ok(v)) => T::from_payload(v.map(|v| *v))
+                .map(Ok)
+                .map_err(|e| e.at_field("ok")),
+            golem_wasm_rpc::Value::Result(Err(e)) => E::from_payload(e.map(|e| *e))
+                .map(Err)
+                .map_err(|e| e.at_field("err")),
+            other => Err(ConversionError::type_mismatch("result", &other)),
         }
     }
 }
 
-impl<T: FromWitValue> FromWitValue for Option<T> {
-    fn from_wit_value(value: WitValue) -> Result<Self, String>
-    where
-        Self: Sized,
-    {
-        let value = golem_wasm_rpc::Value::from(value);
+impl<T: ResultPayload, E: ResultPayload> ToWitType for Result<T, E> {
+    fn get_wit_type() -> WitType {
+        WitType::from(golem_wasm_ast::analysis::AnalysedType::Result(
+            golem_wasm_ast::analysis::TypeResult {
+                name: None,
+                owner: None,
+                ok: T::payload_type().map(Box::new),
+                err: E::payload_type().map(Box::new),
+            },
+        ))
+    }
+}
 
-        match value {
-            golem_wasm_rpc::Value::Option(Some(v)) => {
-                T::from_wit_value(WitValue::from(v.as_ref().clone())).map(Some)
+// Maps have no counterpart in WIT, so they are represented as `list<tuple<k, v>>`
+macro_rules! impl_map {
+    ($map:ident, $($key_bound:path),+) => {
+        impl<K: ToValue, V: ToValue> ToValue for $map<K, V> {
+            fn to_value(&self) -> Value {
+                golem_wasm_rpc::Value::List(
+                    self.iter()
+                        .map(|(k, v)| golem_wasm_rpc::Value::Tuple(vec![k.to_value(), v.to_value()]))
+                        .collect(),
+                )
+            }
+        }
+
+        impl<K: FromValue $(+ $key_bound)+, V: FromValue> FromValue for $map<K, V> {
+            fn from_value(value: Value) -> Result<Self, ConversionError> {
+                match value {
+                    golem_wasm_rpc::Value::List(entries) => decode_list(entries),
+                    other => Err(ConversionError::type_mismatch("list", &other)),
+                }
+            }
+        }
+
+        impl<K: ToWitType, V: ToWitType> ToWitType for $map<K, V> {
+            fn get_wit_type() -> WitType {
+                WitType::from(analysed_type::list(analysed_type::tuple(vec![
+                    wit_type_of::<K>(),
+                    wit_type_of::<V>(),
+                ])))
+            }
+        }
+    };
+}
+
+impl_map!(HashMap, Eq, Hash);
+impl_map!(BTreeMap, Ord);
+
+// Sets are represented as `list<t>`
+macro_rules! impl_set {
+    ($set:ident, $($bound:path),+) => {
+        impl<T: ToValue> ToValue for $set<T> {
+            fn to_value(&self) -> Value {
+                golem_wasm_rpc::Value::List(self.iter().map(ToValue::to_value).collect())
+            }
+        }
+
+        impl<T: FromValue $(+ $bound)+> FromValue for $set<T> {
+            fn from_value(value: Value) -> Result<Self, ConversionError> {
+                match value {
+                    golem_wasm_rpc::Value::List(list) => decode_list(list),
+                    other => Err(ConversionError::type_mismatch("list", &other)),
+                }
+            }
+        }
+
+        impl<T: ToWitType> ToWitType for $set<T> {
+            fn get_wit_type() -> WitType {
+                WitType::from(analysed_type::list(wit_type_of::<T>()))
             }
-            golem_wasm_rpc::Value::Option(None) => Ok(None),
-            _ => Err("Expected an Option WitValue".to_string()),
         }
+    };
+}
+
+impl_set!(HashSet, Eq, Hash);
+impl_set!(BTreeSet, Ord);
+
+// Fixed-size arrays are represented as `list<t>`, and the length is checked when decoding
+impl<T: ToValue, const N: usize> ToValue for [T; N] {
+    fn to_value(&self) -> Value {
+        golem_wasm_rpc::Value::List(self.iter().map(ToValue::to_value).collect())
     }
 }
 
-impl FromWitValue for golem_wasm_rpc::Value {
-    fn from_wit_value(value: WitValue) -> Result<Self, String>
-    where
-        Self: Sized,
-    {
-        Ok(golem_wasm_rpc::Value::from(value))
+impl<T: FromValue, const N: usize> FromValue for [T; N] {
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        let items: Vec<T> = FromValue::from_value(value)?;
+        let len = items.len();
+
+        items
+            .try_into()
+            .map_err(|_| ConversionError::length_mismatch(N, len))
+    }
+}
+
+impl<T: ToWitType, const N: usize> ToWitType for [T; N] {
+    fn get_wit_type() -> WitType {
+        WitType::from(analysed_type::list(wit_type_of::<T>()))
+    }
+}
+
+macro_rules! impl_tuple {
+    ($len:literal => $($name:ident $idx:tt),+) => {
+        impl<$($name: ToValue),+> ToValue for ($($name,)+) {
+            fn to_value(&self) -> Value {
+                golem_wasm_rpc::Value::Tuple(vec![$(self.$idx.to_value()),+])
+            }
+        }
+
+        impl<$($name: FromValue),+> FromValue for ($($name,)+) {
+            fn from_value(value: Value) -> Result<Self, ConversionError> {
+                match value {
+                    golem_wasm_rpc::Value::Tuple(items) if items.len() == $len => {
+                        let mut items = items.into_iter();
+                        Ok(($(
+                            $name::from_value(items.next().unwrap())
+                                .map_err(|e| e.at_index($idx))?,
+                        )+))
+                    }
+                    golem_wasm_rpc::Value::Tuple(items) => {
+                        Err(ConversionError::length_mismatch($len, items.len()))
+                    }
+                    other => Err(ConversionError::type_mismatch("tuple", &other)),
+                }
+            }
+        }
+
+        impl<$($name: ToWitType),+> ToWitType for ($($name,)+) {
+            fn get_wit_type() -> WitType {
+                WitType::from(analysed_type::tuple(vec![$(wit_type_of::<$name>()),+]))
+            }
+        }
+    };
+}
+
+impl_tuple!(1 => A 0);
+impl_tuple!(2 => A 0, B 1);
+impl_tuple!(3 => A 0, B 1, C 2);
+impl_tuple!(4 => A 0, B 1, C 2, D 3);
+impl_tuple!(5 => A 0, B 1, C 2, D 3, E 4);
+impl_tuple!(6 => A 0, B 1, C 2, D 3, E 4, F 5);
+impl_tuple!(7 => A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_tuple!(8 => A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_tuple!(9 => A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_tuple!(10 => A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+
+// Smart pointers are transparent, i.e, they have the same WIT representation as their contents
+macro_rules! impl_transparent_pointer {
+    ($pointer:ident) => {
+        impl<T: ToValue + ?Sized> ToValue for $pointer<T> {
+            fn to_value(&self) -> Value {
+                T::to_value(self)
+            }
+        }
+
+        impl<T: FromValue> FromValue for $pointer<T> {
+            fn from_value(value: Value) -> Result<Self, ConversionError> {
+                T::from_value(value).map($pointer::new)
+            }
+        }
+
+        impl<T: ToWitType + ?Sized> ToWitType for $pointer<T> {
+            fn get_wit_type() -> WitType {
+                T::get_wit_type()
+            }
+        }
+    };
+}
+
+impl_transparent_pointer!(Box);
+impl_transparent_pointer!(Arc);
+
+impl<T: ToValue + ToOwned + ?Sized> ToValue for Cow<'_, T> {
+    fn to_value(&self) -> Value {
+        T::to_value(self)
+    }
+}
+
+impl<T: ToOwned + ?Sized> FromValue for Cow<'_, T>
+where
+    T::Owned: FromValue,
+{
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        T::Owned::from_value(value).map(Cow::Owned)
+    }
+}
+
+impl<T: ToWitType + ToOwned + ?Sized> ToWitType for Cow<'_, T> {
+    fn get_wit_type() -> WitType {
+        T::get_wit_type()
+    }
+}
+
+// Decodes the elements of a list, recording the index of the failing element in the error
+fn decode_list<T: FromValue, C: FromIterator<T>>(items: Vec<Value>) -> Result<C, ConversionError> {
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(idx, item)| T::from_value(item).map_err(|e| e.at_index(idx)))
+        .collect()
+}
+
+fn wit_type_of<T: ToWitType + ?Sized>() -> golem_wasm_ast::analysis::AnalysedType {
+    golem_wasm_ast::analysis::AnalysedType::from(T::get_wit_type())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use golem_wasm_ast::analysis::analysed_type::{list, result_err, tuple, u32, u8};
+    use golem_wasm_ast::analysis::AnalysedType;
+    use std::fmt::Debug;
+
+    fn round_trip<T: ToValue + FromValue + PartialEq + Debug>(value: T) {
+        assert_eq!(T::from_value(ToValue::to_value(&value)).unwrap(), value);
+    }
+
+    fn wit_type<T: ToWitType>() -> AnalysedType {
+        AnalysedType::from(T::get_wit_type())
+    }
+
+    #[test]
+    fn lists_round_trip() {
+        round_trip(vec![1u32, 2, 3]);
+        round_trip(Vec::<String>::new());
+        round_trip(vec![vec!["a".to_string()], vec![]]);
+    }
+
+    #[test]
+    fn options_round_trip() {
+        round_trip(Some(42u8));
+        round_trip(None::<u8>);
+        round_trip(Some(Some("nested".to_string())));
+    }
+
+    #[test]
+    fn results_round_trip() {
+        round_trip(Ok::<u32, String>(7));
+        round_trip(Err::<u32, String>("failed".to_string()));
+        round_trip(Ok::<(), String>(()));
+        round_trip(Err::<(), String>("failed".to_string()));
+        round_trip(Err::<u32, ()>(()));
+    }
+
+    #[test]
+    fn unit_payloads_are_absent() {
+        assert_eq!(ToValue::to_value(&Ok::<(), String>(())), Value::Result(Ok(None)));
+        assert_eq!(wit_type::<Result<(), String>>(), result_err(str()));
+        assert_eq!(
+            Result::<(), String>::from_value(Value::Result(Ok(Some(Box::new(Value::U8(1))))))
+                .unwrap_err()
+                .to_string(),
+            "ok: unexpected payload"
+        );
+        assert_eq!(
+            Result::<u8, String>::from_value(Value::Result(Ok(None)))
+                .unwrap_err()
+                .to_string(),
+            "ok: missing payload"
+        );
+    }
+
+    #[test]
+    fn maps_round_trip() {
+        round_trip(HashMap::from([
+            ("a".to_string(), 1u32),
+            ("b".to_string(), 2),
+        ]));
+        round_trip(BTreeMap::from([(1u8, vec![true]), (2, vec![])]));
+        assert_eq!(
+            wit_type::<BTreeMap<u8, u32>>(),
+            list(tuple(vec![u8(), u32()]))
+        );
+    }
+
+    #[test]
+    fn sets_round_trip() {
+        round_trip(HashSet::from(["a".to_string(), "b".to_string()]));
+        round_trip(BTreeSet::from([3u32, 1, 2]));
+        assert_eq!(wit_type::<BTreeSet<u32>>(), list(u32()));
+    }
+
+    #[test]
+    fn arrays_round_trip() {
+        round_trip([1u8, 2, 3]);
+        round_trip([[true; 2]; 2]);
+        assert_eq!(
+            <[u8; 3]>::from_value(ToValue::to_value(&vec![1u8, 2]))
+                .unwrap_err()
+                .kind,
+            crate::conversion_error::ConversionErrorKind::LengthMismatch {
+                expected: 3,
+                actual: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn tuples_round_trip() {
+        round_trip((1u8,));
+        round_trip((1u8, "two".to_string(), Some(3u32)));
+        round_trip((1u8, 2u16, 3u32, 4u64, 5i8, 6i16, 7i32, 8i64, 'c', true));
+        assert_eq!(
+            <(u8, String)>::from_value(Value::Tuple(vec![Value::U8(1), Value::U8(2)]))
+                .unwrap_err()
+                .to_string(),
+            "[1]: expected string, but found u8"
+        );
     }
 }
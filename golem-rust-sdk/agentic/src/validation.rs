@@ -0,0 +1,423 @@
+// Checks invocation inputs against the data schemas of the registered agent types, before they
+// reach the generated dispatcher. This gives dynamic callers (which only know the schemas) the
+// same errors for every agent, independently of how the agent decodes its arguments.
+
+use crate::bindings::golem::agent::common::{
+    ArityMismatch, DataSchema, Error, ParameterType, TypeMismatch,
+};
+use crate::conversion_error::{wit_type_name, ConversionError};
+use golem_wasm_ast::analysis::AnalysedType;
+use golem_wasm_rpc::{Value, WitValue};
+
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum InputValidationError {
+    #[error("expected {expected} arguments, but got {actual}")]
+    ArityMismatch { expected: usize, actual: usize },
+    #[error("invalid value for parameter '{parameter}': {error}")]
+    TypeMismatch {
+        parameter: String,
+        error: ConversionError,
+    },
+    #[error("{0}")]
+    UnsupportedSchema(String),
+}
+
+impl From<InputValidationError> for Error {
+    fn from(error: InputValidationError) -> Self {
+        match error {
+            InputValidationError::ArityMismatch { expected, actual } => {
+                Error::ArityMismatch(ArityMismatch {
+                    expected: expected as u32,
+                    actual: actual as u32,
+                })
+            }
+            InputValidationError::TypeMismatch { parameter, error } => {
+                Error::TypeMismatch(TypeMismatch {
+                    parameter,
+                    details: error.to_string(),
+                })
+            }
+            InputValidationError::UnsupportedSchema(details) => Error::TypeMismatch(TypeMismatch {
+                parameter: String::new(),
+                details,
+            }),
+        }
+    }
+}
+
+pub fn validate_inputs(
+    schema: &DataSchema,
+    input: &[WitValue],
+) -> Result<(), InputValidationError> {
+    let parameters = match schema {
+        DataSchema::Structured(structured) => &structured.parameters,
+        DataSchema::Multimodal(_) => {
+            return Err(InputValidationError::UnsupportedSchema(
+                "multimodal schemas are not supported".to_string(),
+            ))
+        }
+    };
+
+    if parameters.len() != input.len() {
+        return Err(InputValidationError::ArityMismatch {
+            expected: parameters.len(),
+            actual: input.len(),
+        });
+    }
+
+    for (parameter, value) in parameters.iter().zip(input) {
+        let value = Value::from(value.clone());
+
+        let result = match &parameter.parameter_type {
+            ParameterType::Wit(wit_type) => {
+                validate_value(&value, &AnalysedType::from(wit_type.clone()))
+            }
+            ParameterType::Text(_) => match &value {
+                Value::String(_) => Ok(()),
+                other => Err(ConversionError::type_mismatch("string", other)),
+            },
+        };
+
+        result.map_err(|error| InputValidationError::TypeMismatch {
+            parameter: parameter.name.clone(),
+            error,
+        })?;
+    }
+
+    Ok(())
+}
+
+pub fn validate_value(value: &Value, typ: &AnalysedType) -> Result<(), ConversionError> {
+    match (typ, value) {
+        (AnalysedType::Bool(_), Value::Bool(_))
+        | (AnalysedType::U8(_), Value::U8(_))
+        | (AnalysedType::U16(_), Value::U16(_))
+        | (AnalysedType::U32(_), Value::U32(_))
+        | (AnalysedType::U64(_), Value::U64(_))
+        | (AnalysedType::S8(_), Value::S8(_))
+        | (AnalysedType::S16(_), Value::S16(_))
+        | (AnalysedType::S32(_), Value::S32(_))
+        | (AnalysedType::S64(_), Value::S64(_))
+        | (AnalysedType::F32(_), Value::F32(_))
+        | (AnalysedType::F64(_), Value::F64(_))
+        | (AnalysedType::Chr(_), Value::Char(_))
+        | (AnalysedType::Str(_), Value::String(_))
+        | (AnalysedType::Handle(_), Value::Handle { .. })
+        | (AnalysedType::Option(_), Value::Option(None)) => Ok(()),
+        (AnalysedType::List(list), Value::List(items)) => {
+            for (idx, item) in items.iter().enumerate() {
+                validate_value(item, &list.inner).map_err(|e| e.at_index(idx))?;
+            }
+            Ok(())
+        }
+        (AnalysedType::Tuple(tuple), Value::Tuple(items)) => {
+            if items.len() != tuple.items.len() {
+                return Err(ConversionError::length_mismatch(
+                    tuple.items.len(),
+                    items.len(),
+                ));
+            }
+
+            for (idx, (item, typ)) in items.iter().zip(&tuple.items).enumerate() {
+                validate_value(item, typ).map_err(|e| e.at_index(idx))?;
+            }
+            Ok(())
+        }
+        // Trailing fields can be missing, as records deriving `AgentArg` fill them in if they
+        // have a default. Schemas do not tell which fields have one, so missing fields without
+        // a default are left to be reported when decoding the record.
+        (AnalysedType::Record(record), Value::Record(values)) => {
+            if values.len() > record.fields.len() {
+                return Err(ConversionError::length_mismatch(
+                    record.fields.len(),
+                    values.len(),
+                ));
+            }
+
+            for (field, value) in record.fields.iter().zip(values) {
+                validate_value(value, &field.typ).map_err(|e| e.at_field(field.name.clone()))?;
+            }
+            Ok(())
+        }
+        (
+            AnalysedType::Variant(variant),
+            Value::Variant {
+                case_idx,
+                case_value,
+            },
+        ) => {
+            let case = variant
+                .cases
+                .get(*case_idx as usize)
+                .ok_or_else(|| ConversionError::unknown_case("variant", *case_idx))?;
+
+            match (&case.typ, case_value) {
+                (None, None) => Ok(()),
+                (Some(typ), Some(payload)) => {
+                    validate_value(payload, typ).map_err(|e| e.at_field(case.name.clone()))
+                }
+                (None, Some(_)) => {
+                    Err(ConversionError::custom("unexpected payload").at_field(case.name.clone()))
+                }
+                (Some(_), None) => {
+                    Err(ConversionError::custom("missing payload").at_field(case.name.clone()))
+                }
+            }
+        }
+        (AnalysedType::Enum(enum_type), Value::Enum(case_idx)) => {
+            if (*case_idx as usize) < enum_type.cases.len() {
+                Ok(())
+            } else {
+                Err(ConversionError::unknown_case("enum", *case_idx))
+            }
+        }
+        (AnalysedType::Flags(flags), Value::Flags(set)) => {
+            if set.len() == flags.names.len() {
+                Ok(())
+            } else {
+                Err(ConversionError::length_mismatch(
+                    flags.names.len(),
+                    set.len(),
+                ))
+            }
+        }
+        (AnalysedType::Option(option), Value::Option(Some(inner))) => {
+            validate_value(inner, &option.inner)
+        }
+        (AnalysedType::Result(result), Value::Result(value)) => {
+            let (key, typ, payload) = match value {
+                Ok(payload) => ("ok", &result.ok, payload),
+                Err(payload) => ("err", &result.err, payload),
+            };
+
+            match (typ, payload) {
+                (None, None) => Ok(()),
+                (Some(typ), Some(payload)) => {
+                    validate_value(payload, typ).map_err(|e| e.at_field(key))
+                }
+                (None, Some(_)) => Err(ConversionError::custom("unexpected payload").at_field(key)),
+                (Some(_), None) => Err(ConversionError::custom("missing payload").at_field(key)),
+            }
+        }
+        (typ, value) => Err(ConversionError::type_mismatch(wit_type_name(typ), value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::golem::agent::common::{
+        Multimodal, NamedParameterType, Structured, TextType,
+    };
+    use crate::conversion_error::ConversionErrorKind;
+    use golem_wasm_ast::analysis::analysed_type::{
+        case, field, flags, list, option, r#enum, record, result, str, tuple, u32, u8, unit_case,
+        variant,
+    };
+
+    fn schema(parameters: Vec<(&str, ParameterType)>) -> DataSchema {
+        DataSchema::Structured(Structured {
+            parameters: parameters
+                .into_iter()
+                .map(|(name, parameter_type)| NamedParameterType {
+                    name: name.to_string(),
+                    parameter_type,
+                })
+                .collect(),
+        })
+    }
+
+    fn wit(typ: AnalysedType) -> ParameterType {
+        ParameterType::Wit(typ.into())
+    }
+
+    #[test]
+    fn matching_inputs_are_accepted() {
+        let schema = schema(vec![
+            ("city", wit(str())),
+            ("days", wit(option(u8()))),
+            (
+                "note",
+                ParameterType::Text(TextType {
+                    language_code: "en".to_string(),
+                }),
+            ),
+        ]);
+
+        let input = vec![
+            WitValue::from(Value::String("Berlin".to_string())),
+            WitValue::from(Value::Option(Some(Box::new(Value::U8(3))))),
+            WitValue::from(Value::String("sunny".to_string())),
+        ];
+
+        assert_eq!(validate_inputs(&schema, &input), Ok(()));
+    }
+
+    #[test]
+    fn arity_mismatch_is_rejected() {
+        let schema = schema(vec![("city", wit(str())), ("days", wit(u8()))]);
+
+        let input = vec![WitValue::from(Value::String("Berlin".to_string()))];
+
+        assert_eq!(
+            validate_inputs(&schema, &input),
+            Err(InputValidationError::ArityMismatch {
+                expected: 2,
+                actual: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn type_mismatch_names_the_parameter() {
+        let schema = schema(vec![("city", wit(str())), ("days", wit(u8()))]);
+
+        let input = vec![
+            WitValue::from(Value::String("Berlin".to_string())),
+            WitValue::from(Value::String("three".to_string())),
+        ];
+
+        let error = validate_inputs(&schema, &input).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "invalid value for parameter 'days': expected u8, but found string"
+        );
+    }
+
+    #[test]
+    fn multimodal_schemas_are_rejected() {
+        let schema = DataSchema::Multimodal(Multimodal { text: None });
+
+        assert!(matches!(
+            validate_inputs(&schema, &[]),
+            Err(InputValidationError::UnsupportedSchema(_))
+        ));
+    }
+
+    #[test]
+    fn nested_errors_point_to_the_value() {
+        let typ = record(vec![field("items", list(tuple(vec![str(), u32()])))]);
+        let value = Value::Record(vec![Value::List(vec![
+            Value::Tuple(vec![Value::String("a".to_string()), Value::U32(1)]),
+            Value::Tuple(vec![Value::String("b".to_string()), Value::U8(2)]),
+        ])]);
+
+        let error = validate_value(&value, &typ).unwrap_err();
+
+        assert_eq!(error.path_string(), "items[1][1]");
+        assert_eq!(
+            error.kind,
+            ConversionErrorKind::TypeMismatch {
+                expected: "u32".to_string(),
+                actual: "u8".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn records_may_miss_trailing_fields() {
+        let typ = record(vec![field("city", str()), field("days", u8())]);
+
+        let value = Value::Record(vec![Value::String("Berlin".to_string())]);
+
+        assert_eq!(validate_value(&value, &typ), Ok(()));
+    }
+
+    #[test]
+    fn records_with_extra_fields_are_rejected() {
+        let typ = record(vec![field("city", str())]);
+
+        let value = Value::Record(vec![Value::String("Berlin".to_string()), Value::U8(3)]);
+
+        assert_eq!(
+            validate_value(&value, &typ).unwrap_err().kind,
+            ConversionErrorKind::LengthMismatch {
+                expected: 1,
+                actual: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn variant_payloads_must_match_the_case() {
+        let typ = variant(vec![case("celsius", u8()), unit_case("unknown")]);
+
+        let valid = Value::Variant {
+            case_idx: 0,
+            case_value: Some(Box::new(Value::U8(21))),
+        };
+        let missing_payload = Value::Variant {
+            case_idx: 0,
+            case_value: None,
+        };
+        let unexpected_payload = Value::Variant {
+            case_idx: 1,
+            case_value: Some(Box::new(Value::U8(21))),
+        };
+        let unknown_case = Value::Variant {
+            case_idx: 2,
+            case_value: None,
+        };
+
+        assert_eq!(validate_value(&valid, &typ), Ok(()));
+        assert_eq!(
+            validate_value(&missing_payload, &typ)
+                .unwrap_err()
+                .to_string(),
+            "celsius: missing payload"
+        );
+        assert_eq!(
+            validate_value(&unexpected_payload, &typ)
+                .unwrap_err()
+                .to_string(),
+            "unknown: unexpected payload"
+        );
+        assert_eq!(
+            validate_value(&unknown_case, &typ).unwrap_err().kind,
+            ConversionErrorKind::UnknownCase {
+                type_name: "variant".to_string(),
+                case_idx: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn enums_and_flags_are_checked_against_their_cases() {
+        let enum_type = r#enum(&["low", "high"]);
+        let flags_type = flags(&["read", "write"]);
+
+        assert_eq!(validate_value(&Value::Enum(1), &enum_type), Ok(()));
+        assert!(validate_value(&Value::Enum(2), &enum_type).is_err());
+        assert_eq!(
+            validate_value(&Value::Flags(vec![true, false]), &flags_type),
+            Ok(())
+        );
+        assert!(validate_value(&Value::Flags(vec![true]), &flags_type).is_err());
+    }
+
+    #[test]
+    fn result_payloads_are_validated() {
+        let typ = result(str(), u8());
+
+        assert_eq!(
+            validate_value(
+                &Value::Result(Ok(Some(Box::new(Value::String("done".to_string()))))),
+                &typ
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            validate_value(&Value::Result(Err(Some(Box::new(Value::U32(1))))), &typ)
+                .unwrap_err()
+                .to_string(),
+            "err: expected u8, but found u32"
+        );
+        assert_eq!(
+            validate_value(&Value::Result(Ok(None)), &typ)
+                .unwrap_err()
+                .to_string(),
+            "ok: missing payload"
+        );
+    }
+}
@@ -25,6 +25,7 @@ import * as Either from './newTypes/either';
 import {
   getAgentMethodSchema,
   getConstructorDataSchema,
+  hasReturnValue,
 } from './internal/schema';
 import * as Option from './newTypes/option';
 import { AgentMethodMetadataRegistry } from './internal/registry/agentMethodMetadataRegistry';
@@ -279,12 +280,43 @@ export function agent() {
 
               const returnType: Type = methodInfo.returnType;
 
-              const paramTypeArray = Array.from(paramTypes.values());
+              const paramEntries = Array.from(paramTypes.entries());
 
-              const convertedArgs = argsWitValues.map((witVal, idx) => {
-                const paramType = paramTypeArray[idx];
-                return WitValue.toTsValue(witVal, paramType);
-              });
+              if (argsWitValues.length !== paramEntries.length) {
+                const error: AgentError = {
+                  tag: 'arity-mismatch',
+                  val: {
+                    expected: paramEntries.length,
+                    actual: argsWitValues.length,
+                  },
+                };
+                return {
+                  tag: 'err',
+                  val: error,
+                };
+              }
+
+              const convertedArgs: any[] = [];
+
+              for (const [idx, witVal] of argsWitValues.entries()) {
+                const [paramName, paramType] = paramEntries[idx];
+
+                try {
+                  convertedArgs.push(WitValue.toTsValue(witVal, paramType));
+                } catch (e) {
+                  const error: AgentError = {
+                    tag: 'type-mismatch',
+                    val: {
+                      parameter: paramName,
+                      details: e instanceof Error ? e.message : String(e),
+                    },
+                  };
+                  return {
+                    tag: 'err',
+                    val: error,
+                  };
+                }
+              }
 
               const result = await fn.apply(instance, convertedArgs);
 
@@ -313,6 +345,16 @@ export function agent() {
                 };
               }
 
+              if (!hasReturnValue(returnType)) {
+                return {
+                  tag: 'ok',
+                  val: {
+                    tag: 'tuple',
+                    val: [],
+                  },
+                };
+              }
+
               const returnValue = WitValue.fromTsValue(result, returnType);
 
               if (Either.isLeft(returnValue)) {
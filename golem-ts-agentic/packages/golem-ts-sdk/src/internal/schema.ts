@@ -109,6 +109,8 @@ export function getAgentMethodSchema(
         promptHint: baseMeta.prompt ?? '',
         inputSchema: inputSchema,
         outputSchema: outputSchema,
+        // Failures of TypeScript methods are not typed, they are thrown
+        errorSchema: undefined,
       });
     }),
   );
@@ -142,6 +144,13 @@ export function buildInputSchema(
 export function buildOutputSchema(
   returnType: Type.Type,
 ): Either.Either<DataSchema, string> {
+  if (!hasReturnValue(returnType)) {
+    return Either.right({
+      tag: 'tuple',
+      val: [],
+    });
+  }
+
   return Either.map(convertToElementSchema(returnType), (result) => {
     return {
       tag: 'tuple',
@@ -150,6 +159,20 @@ export function buildOutputSchema(
   });
 }
 
+// Methods without a return value have no element in their output,
+// rather than an element holding an empty tuple
+export function hasReturnValue(returnType: Type.Type): boolean {
+  switch (returnType.kind) {
+    case 'void':
+    case 'undefined':
+      return false;
+    case 'promise':
+      return hasReturnValue(returnType.element);
+    default:
+      return true;
+  }
+}
+
 function convertToElementSchema(
   type: Type.Type,
 ): Either.Either<ElementSchema, string> {
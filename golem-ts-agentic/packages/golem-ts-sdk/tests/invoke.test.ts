@@ -150,6 +150,66 @@ test('WeatherAgent can be successfully initiated and the methods can be invoked'
   );
 });
 
+test('Invoking a method with mismatching arguments fails with typed errors', async () => {
+  overrideSelfMetadataImpl();
+
+  const typeRegistry = TypeMetadata.get(WeatherAgentClassName.value);
+
+  if (!typeRegistry) {
+    throw new Error('WeatherAgent type metadata not found');
+  }
+
+  const constructorWitValue = Either.getOrThrowWith(
+    WitValue.fromTsValue('data', typeRegistry.constructorArgs[0].type),
+    (error) =>
+      new Error(`Failed to convert constructor arg to WitValue. ${error}`),
+  );
+
+  const agentInitiator = Option.getOrThrowWith(
+    AgentInitiatorRegistry.lookup(WeatherAgentName),
+    () => new Error('WeatherAgent not found in AgentInitiatorRegistry'),
+  );
+
+  const result = agentInitiator.initiate(
+    WeatherAgentName.value,
+    getDataValueFromWitValue(constructorWitValue),
+  );
+
+  if (result.tag !== 'ok') {
+    throw new Error('Agent initiation failed');
+  }
+
+  const arityResult = await result.val.invoke('getWeather', {
+    tag: 'tuple',
+    val: [],
+  });
+
+  expect(arityResult).toEqual({
+    tag: 'err',
+    val: { tag: 'arity-mismatch', val: { expected: 1, actual: 0 } },
+  });
+
+  const numberWitValue = Either.getOrThrowWith(
+    WitValue.fromTsValue(42, { kind: 'number' }),
+    (error) => new Error(`Failed to convert method arg to WitValue. ${error}`),
+  );
+
+  const typeResult = await result.val.invoke(
+    'getWeather',
+    getDataValueFromWitValue(numberWitValue),
+  );
+
+  expect(typeResult.tag).toEqual('err');
+  expect(typeResult.tag === 'err' && typeResult.val.tag).toEqual(
+    'type-mismatch',
+  );
+  expect(
+    typeResult.tag === 'err' &&
+      typeResult.val.tag === 'type-mismatch' &&
+      typeResult.val.val.parameter,
+  ).toEqual('location');
+});
+
 function testInvoke(
   typeRegistry: ClassMetadata,
   methodName: string,
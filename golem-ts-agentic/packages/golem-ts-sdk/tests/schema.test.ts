@@ -0,0 +1,47 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Golem Source License v1.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://license.golem.cloud/LICENSE
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+import { describe, it, expect } from 'vitest';
+import { Type } from '@golemcloud/golem-ts-types-core';
+import { buildOutputSchema } from '../src/internal/schema';
+import * as Either from '../src/newTypes/either';
+
+function outputSchema(returnType: Type.Type) {
+  return Either.getOrThrowWith(
+    buildOutputSchema(returnType),
+    (err) => new Error(`Failed to construct output schema: ${err}`),
+  );
+}
+
+describe('Output schema of agent methods', () => {
+  it('has a single element for methods returning a value', () => {
+    const schema = outputSchema({
+      kind: 'promise',
+      element: { kind: 'string' },
+    });
+
+    expect(schema.tag).toEqual('tuple');
+    expect(schema.val.map(([name]) => name)).toEqual(['return-value']);
+  });
+
+  it('has no elements for methods without a return value', () => {
+    expect(outputSchema({ kind: 'void' })).toEqual({ tag: 'tuple', val: [] });
+    expect(
+      outputSchema({ kind: 'promise', element: { kind: 'void' } }),
+    ).toEqual({ tag: 'tuple', val: [] });
+    expect(
+      outputSchema({ kind: 'promise', element: { kind: 'undefined' } }),
+    ).toEqual({ tag: 'tuple', val: [] });
+  });
+});
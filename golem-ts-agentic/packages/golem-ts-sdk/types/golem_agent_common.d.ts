@@ -54,6 +54,7 @@ declare module 'golem:agent/common' {
     promptHint: string | undefined;
     inputSchema: DataSchema;
     outputSchema: DataSchema;
+    errorSchema: DataSchema | undefined;
   };
   export type AgentConstructor = {
     name: string | undefined;
@@ -106,6 +107,14 @@ declare module 'golem:agent/common' {
     tag: 'multimodal'
     val: [string, ElementValue][]
   };
+  export type ArityMismatch = {
+    expected: number;
+    actual: number;
+  };
+  export type TypeMismatch = {
+    parameter: string;
+    details: string;
+  };
   export type AgentError = {
     tag: 'invalid-input'
     val: string
@@ -125,5 +134,17 @@ declare module 'golem:agent/common' {
   {
     tag: 'custom-error'
     val: ValueAndType
+  } |
+  {
+    tag: 'application-error'
+    val: DataValue
+  } |
+  {
+    tag: 'arity-mismatch'
+    val: ArityMismatch
+  } |
+  {
+    tag: 'type-mismatch'
+    val: TypeMismatch
   };
 }